@@ -1,12 +1,10 @@
--- Database init script for Zwei
+-- Initial database schema for Zwei
 -- Author: Riven Skaye
 -- Project: Zwei
 
--- PRAGMAs for sqlite3
--- use FKs
-PRAGMA foreign_keys = ON;
--- Write Ahead Logging, for increased throughput from async code
-PRAGMA journal_mode = WAL;
+-- Applied by Zwei on startup, along with the migrations after it. Foreign keys
+-- and WAL mode are set on the connection, as they can't be changed from
+-- within the transaction a migration runs in.
 
 
 -- Prefixes the bot will respond to
@@ -0,0 +1,7 @@
+-- Warnings remember who issued them and when. SQLite won't add a column with
+-- a non-constant default, so the time is filled in on insert instead.
+-- Warnings from before this have their time set to now, so expiry doesn't
+-- throw them out all at once.
+ALTER TABLE 'warnings' ADD COLUMN 'moderator' INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE 'warnings' ADD COLUMN 'issued' INTEGER NOT NULL DEFAULT 0;
+UPDATE 'warnings' SET 'issued' = strftime('%s', 'now');
@@ -0,0 +1,14 @@
+-- Moderation settings per guild
+CREATE TABLE IF NOT EXISTS 'modconf'(
+    'serverid' INTEGER PRIMARY KEY NOT NULL,
+    'warn_expiry' INTEGER NOT NULL DEFAULT 0
+);
+
+-- Actions taken automatically when a user reaches a certain amount of warnings
+CREATE TABLE IF NOT EXISTS 'escalations'(
+    'serverid' INTEGER NOT NULL,
+    'threshold' INTEGER NOT NULL,
+    'action' VARCHAR(16) NOT NULL,
+    'duration' INTEGER NOT NULL DEFAULT 0,
+    UNIQUE('serverid', 'threshold') ON CONFLICT REPLACE
+);
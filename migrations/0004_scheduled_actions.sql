@@ -0,0 +1,8 @@
+-- Actions to perform at a later time, like lifting a temporary ban
+CREATE TABLE IF NOT EXISTS 'scheduled_actions'(
+    'actionid' INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'target' INTEGER NOT NULL,
+    'action' VARCHAR(16) NOT NULL,
+    'due' INTEGER NOT NULL
+);
@@ -0,0 +1,9 @@
+-- The muted role, used for mutes longer than a timeout can last. 0 for none.
+ALTER TABLE 'modconf' ADD COLUMN 'muterole' INTEGER NOT NULL DEFAULT 0;
+
+-- Users muted through the muted role, so it can be reapplied when they rejoin
+CREATE TABLE IF NOT EXISTS 'mutes'(
+    'serverid' INTEGER NOT NULL,
+    'userid' INTEGER NOT NULL,
+    UNIQUE('serverid', 'userid') ON CONFLICT REPLACE
+);
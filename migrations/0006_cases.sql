@@ -0,0 +1,16 @@
+-- The channel cases are logged to, 0 for none.
+ALTER TABLE 'modconf' ADD COLUMN 'modlog' INTEGER NOT NULL DEFAULT 0;
+
+-- Numbered moderation cases per guild. The target is a channel for purges.
+CREATE TABLE IF NOT EXISTS 'cases'(
+    'caseid' INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'caseno' INTEGER NOT NULL,
+    'moderator' INTEGER NOT NULL,
+    'target' INTEGER NOT NULL,
+    'action' VARCHAR(16) NOT NULL,
+    'reason' LONGTEXT NOT NULL DEFAULT '',
+    'created' INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    'logmsg' INTEGER NOT NULL DEFAULT 0,
+    UNIQUE('serverid', 'caseno') ON CONFLICT FAIL
+);
@@ -0,0 +1,10 @@
+-- Anti-raid settings per guild. A threshold of 0 joins disables join tracking,
+-- and the previous verification level is restored when raid mode is lifted.
+CREATE TABLE IF NOT EXISTS 'antiraid'(
+    'serverid' INTEGER PRIMARY KEY NOT NULL,
+    'joins' INTEGER NOT NULL DEFAULT 0,
+    'per_secs' INTEGER NOT NULL DEFAULT 10,
+    'min_age' INTEGER NOT NULL DEFAULT 0,
+    'active' INTEGER NOT NULL DEFAULT 0,
+    'prev_level' INTEGER NOT NULL DEFAULT 0
+);
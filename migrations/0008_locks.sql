@@ -0,0 +1,9 @@
+-- The @everyone overwrite a channel had before it was locked, so unlocking can
+-- restore it exactly. 'existed' is 0 if the channel had no such overwrite.
+CREATE TABLE IF NOT EXISTS 'locks'(
+    'channelid' INTEGER PRIMARY KEY NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'allow_bits' INTEGER NOT NULL DEFAULT 0,
+    'deny_bits' INTEGER NOT NULL DEFAULT 0,
+    'existed' INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,2 @@
+-- 'data' holds anything extra an action needs, like the slowmode to go back to.
+ALTER TABLE 'scheduled_actions' ADD COLUMN 'data' INTEGER NOT NULL DEFAULT 0;
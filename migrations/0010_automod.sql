@@ -0,0 +1,18 @@
+-- Automod rules per guild. 'kind' is one of word, regex, zalgo, caps or invite,
+-- and 'pattern' is only used by word and regex rules. 'duration' is in seconds
+-- and only used by the mute action.
+CREATE TABLE IF NOT EXISTS 'automod_rules'(
+    'ruleid' INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'kind' VARCHAR(16) NOT NULL,
+    'pattern' LONGTEXT NOT NULL DEFAULT '',
+    'action' VARCHAR(16) NOT NULL,
+    'duration' INTEGER NOT NULL DEFAULT 0
+);
+
+-- Roles whose members automod leaves alone
+CREATE TABLE IF NOT EXISTS 'automod_exempt'(
+    'serverid' INTEGER NOT NULL,
+    'roleid' INTEGER NOT NULL,
+    UNIQUE('serverid', 'roleid') ON CONFLICT IGNORE
+);
@@ -0,0 +1,20 @@
+-- Anti-spam settings per guild. A limit of 0 turns that check off. 'max_msgs'
+-- and 'max_dupes' count messages within 'per_secs' seconds, 'max_mentions'
+-- counts mentions in a single message. 'duration' is only used by the mute
+-- action.
+CREATE TABLE IF NOT EXISTS 'antispam'(
+    'serverid' INTEGER PRIMARY KEY NOT NULL,
+    'max_msgs' INTEGER NOT NULL DEFAULT 0,
+    'max_dupes' INTEGER NOT NULL DEFAULT 0,
+    'max_mentions' INTEGER NOT NULL DEFAULT 0,
+    'per_secs' INTEGER NOT NULL DEFAULT 5,
+    'action' VARCHAR(16) NOT NULL DEFAULT 'mute',
+    'duration' INTEGER NOT NULL DEFAULT 600
+);
+
+-- Channels anti-spam leaves alone
+CREATE TABLE IF NOT EXISTS 'antispam_exempt'(
+    'serverid' INTEGER NOT NULL,
+    'channelid' INTEGER NOT NULL,
+    UNIQUE('serverid', 'channelid') ON CONFLICT IGNORE
+);
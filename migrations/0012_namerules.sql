@@ -0,0 +1,17 @@
+-- Name rules per guild. Members whose display name breaks one of the enabled
+-- rules get renamed to 'placeholder'. 'dehoist' strips leading symbols used
+-- to get to the top of the member list instead.
+CREATE TABLE IF NOT EXISTS 'namerules'(
+    'serverid' INTEGER PRIMARY KEY NOT NULL,
+    'placeholder' VARCHAR(32) NOT NULL DEFAULT 'Moderated Nickname',
+    'dehoist' INTEGER NOT NULL DEFAULT 0,
+    'ascii' INTEGER NOT NULL DEFAULT 0,
+    'zalgo' INTEGER NOT NULL DEFAULT 0
+);
+
+-- Patterns display names may not match
+CREATE TABLE IF NOT EXISTS 'name_denylist'(
+    'patternid' INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'pattern' LONGTEXT NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Private notes staff keep on users per guild. Unlike warnings, these are
+-- never shown to the user and don't count towards escalations.
+CREATE TABLE IF NOT EXISTS 'notes'(
+    'noteid' INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'userid' INTEGER NOT NULL,
+    'author' INTEGER NOT NULL,
+    'created' INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    'note' LONGTEXT NOT NULL
+);
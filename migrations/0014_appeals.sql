@@ -0,0 +1,19 @@
+-- The channel appeals are posted to, 0 while the guild takes no appeals.
+ALTER TABLE 'modconf' ADD COLUMN 'appeals' INTEGER NOT NULL DEFAULT 0;
+
+-- Ban appeals. One is opened with status 'pending' for every ban while the
+-- guild has an appeals channel, and its ID is sent along with the ban notice.
+-- Submitting it makes it 'open' until staff mark it 'accepted' or 'denied'.
+-- 'reason' is the reason for the ban, 'message' is the appeal itself.
+CREATE TABLE IF NOT EXISTS 'appeals'(
+    'appealid' INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'userid' INTEGER NOT NULL,
+    'reason' LONGTEXT NOT NULL DEFAULT '',
+    'message' LONGTEXT NOT NULL DEFAULT '',
+    'status' VARCHAR(16) NOT NULL DEFAULT 'pending',
+    'created' INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    'submitted' INTEGER NOT NULL DEFAULT 0,
+    'moderator' INTEGER NOT NULL DEFAULT 0,
+    'logmsg' INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,9 @@
+-- Tags get a description, creator, creation date and emoji. 'creator' is
+-- whoever added the tag, 'emoji' is either a unicode emoji or a custom one as
+-- <:name:id>, or empty for none. SQLite won't add a column with a
+-- non-constant default, so the creation date is filled in on insert. Tags
+-- from before this show 0 as creator and creation date, which reads as unknown.
+ALTER TABLE 'servertags' ADD COLUMN 'description' LONGTEXT NOT NULL DEFAULT '';
+ALTER TABLE 'servertags' ADD COLUMN 'creator' INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE 'servertags' ADD COLUMN 'created' INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE 'servertags' ADD COLUMN 'emoji' VARCHAR(64) NOT NULL DEFAULT '';
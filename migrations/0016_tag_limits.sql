@@ -0,0 +1,23 @@
+-- Only members with 'pingrole' may ping the tag, unless it's 0.
+ALTER TABLE 'servertags' ADD COLUMN 'pingrole' INTEGER NOT NULL DEFAULT 0;
+
+-- Limits on pinging tags per guild. Cooldowns are in seconds, the quota is
+-- the amount of tags a member may ping per day. 0 turns a limit off.
+CREATE TABLE IF NOT EXISTS 'tagconf'(
+    'serverid' INTEGER PRIMARY KEY NOT NULL,
+    'tag_cooldown' INTEGER NOT NULL DEFAULT 0,
+    'user_cooldown' INTEGER NOT NULL DEFAULT 0,
+    'daily_quota' INTEGER NOT NULL DEFAULT 0
+);
+
+-- Every time a tag was pinged, one row per tag. Kept when the tag is removed,
+-- which is why the name is stored alongside the ID.
+CREATE TABLE IF NOT EXISTS 'tagpings'(
+    'pingid' INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'tagid' INTEGER NOT NULL,
+    'tagname' LONGTEXT NOT NULL,
+    'userid' INTEGER NOT NULL,
+    'channelid' INTEGER NOT NULL,
+    'pinged' INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
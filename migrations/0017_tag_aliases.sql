@@ -0,0 +1,9 @@
+-- Other names for tags. Pinging or subscribing to an alias works the same as
+-- using the tag itself. Aliases can't share a name with a tag in the guild.
+CREATE TABLE IF NOT EXISTS 'tagaliases'(
+    'serverid' INTEGER NOT NULL,
+    'alias' LONGTEXT NOT NULL,
+    'tagid' INTEGER NOT NULL,
+    UNIQUE('serverid', 'alias') ON CONFLICT FAIL,
+    FOREIGN KEY ('tagid') REFERENCES 'servertags'('tagid') ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+-- Messages with a menu to subscribe to tags with. Kept so they can be
+-- brought up to date when tags change or Zwei restarts.
+CREATE TABLE IF NOT EXISTS 'tagpanels'(
+    'messageid' INTEGER PRIMARY KEY NOT NULL,
+    'serverid' INTEGER NOT NULL,
+    'channelid' INTEGER NOT NULL
+);
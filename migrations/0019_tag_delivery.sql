@@ -0,0 +1,3 @@
+-- How subscribers want to hear about pings: 'channel' for a mention, 'dm' for
+-- a DM or 'both'.
+ALTER TABLE 'tagsubs' ADD COLUMN 'delivery' VARCHAR(8) NOT NULL DEFAULT 'channel';
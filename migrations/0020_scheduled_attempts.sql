@@ -0,0 +1,3 @@
+-- 'attempts' counts how often a scheduled action failed before and got
+-- pushed back.
+ALTER TABLE 'scheduled_actions' ADD COLUMN 'attempts' INTEGER NOT NULL DEFAULT 0;
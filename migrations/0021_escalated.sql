@@ -0,0 +1,9 @@
+-- The highest escalation threshold already applied to a member, so lower
+-- rules don't fire again when warnings expire or get removed. Cleared along
+-- with all their warnings.
+CREATE TABLE IF NOT EXISTS 'escalated'(
+    'serverid' INTEGER NOT NULL,
+    'userid' INTEGER NOT NULL,
+    'threshold' INTEGER NOT NULL,
+    UNIQUE('serverid', 'userid') ON CONFLICT REPLACE
+);
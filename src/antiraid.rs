@@ -0,0 +1,194 @@
+use chrono::Utc;
+use serenity::{framework::standard::CommandResult, model::prelude::*, prelude::*};
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    commands::modtools::kick_member,
+    dbx::{self, ZweiDbConn},
+    fmt_duration,
+    modlog::{alert, log_case},
+};
+
+/// # ZweiJoins
+/// A `TypeMapKey` holding recent join times per guild, used to tell a raid
+/// apart from a normal busy day. Only joins within the guild's window are kept.
+pub struct ZweiJoins;
+impl TypeMapKey for ZweiJoins {
+    type Value = HashMap<u64, VecDeque<i64>>;
+}
+
+/// # level_from
+/// Turns a stored verification level back into Serenity's enum.
+fn level_from(level: i64) -> VerificationLevel {
+    match level {
+        1 => VerificationLevel::Low,
+        2 => VerificationLevel::Medium,
+        3 => VerificationLevel::High,
+        4 => VerificationLevel::Higher,
+        _ => VerificationLevel::None,
+    }
+}
+
+/// # on_join
+/// Runs every new member past the guild's anti-raid settings. While raid mode
+/// is on they're kicked right away. Otherwise young accounts are flagged in
+/// the mod-log, and the join is counted towards the raid threshold.
+pub async fn on_join(ctx: &Context, member: &Member) {
+    let guild = member.guild_id;
+    let conf = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => match dbx::get_antiraid(conn, guild.0).await {
+                Ok(conf) => conf,
+                Err(_) => return,
+            },
+            None => return,
+        }
+    };
+    let target = member.user.id;
+
+    if conf.active {
+        let reason = "Raid mode is active, please try again later.";
+        match kick_member(ctx, guild, target, reason).await {
+            Ok(()) => {
+                let me = ctx.cache.current_user_id();
+                log_case(ctx, guild, me, target.0, "kick", reason).await;
+            }
+            Err(e) => log::warn!(
+                "Couldn't kick {} from {} during raid mode\n\t{e}",
+                target.0,
+                guild.0
+            ),
+        }
+        return;
+    }
+
+    let now = Utc::now().timestamp();
+    let created = target.created_at().unix_timestamp();
+    if conf.min_age > 0 && now - created < conf.min_age {
+        alert(
+            ctx,
+            guild,
+            "New account joined",
+            &format!(
+                "<@{0}> (`{0}`) joined with an account created <t:{created}:R>.",
+                target.0
+            ),
+        )
+        .await;
+    }
+
+    if conf.joins < 1 {
+        return;
+    }
+    let recent = {
+        let mut botdata = ctx.data.write().await;
+        let joins = botdata
+            .entry::<ZweiJoins>()
+            .or_default()
+            .entry(guild.0)
+            .or_default();
+        joins.push_back(now);
+        while joins.front().is_some_and(|t| *t <= now - conf.per_secs) {
+            joins.pop_front();
+        }
+        let recent = joins.len();
+        if recent as i64 >= conf.joins {
+            joins.clear();
+        }
+        recent
+    };
+    if recent as i64 >= conf.joins {
+        let why = format!(
+            "{recent} members joined within {}",
+            fmt_duration(conf.per_secs as u64)
+        );
+        if let Err(e) = enable_raid_mode(ctx, guild, &why).await {
+            log::error!("Couldn't enable raid mode in {}\n\t{e}", guild.0);
+        }
+    }
+}
+
+/// # enable_raid_mode
+/// Turns on raid mode: new joiners get kicked and the verification level is
+/// raised to at least `High`. The mod-log is told why it happened. Returns
+/// `false` if raid mode was already on, leaving the level it has to go back
+/// to alone.
+pub async fn enable_raid_mode(ctx: &Context, mut guild: GuildId, why: &str) -> CommandResult<bool> {
+    let prev = match guild.to_guild_cached(ctx) {
+        Some(g) => g.verification_level,
+        None => guild.to_partial_guild(ctx).await?.verification_level,
+    };
+    {
+        let botdata = ctx.data.read().await;
+        let conn = match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn,
+            None => return Err("no database connection".into()),
+        };
+        if dbx::set_raid_mode(conn, guild.0, true, prev.num() as i64).await? == 0 {
+            return Ok(false);
+        }
+    }
+    let raised = if prev < VerificationLevel::High {
+        match guild
+            .edit(ctx, |g| g.verification_level(VerificationLevel::High))
+            .await
+        {
+            Ok(_) => "I raised the verification level and will",
+            Err(e) => {
+                log::warn!(
+                    "Couldn't raise the verification level of {}\n\t{e}",
+                    guild.0
+                );
+                "I couldn't raise the verification level, but I will"
+            }
+        }
+    } else {
+        "I will"
+    };
+    alert(
+        ctx,
+        guild,
+        "Raid mode enabled",
+        &format!("{why}. {raised} kick anyone who joins until raid mode is turned off with `antiraid off`."),
+    )
+    .await;
+    Ok(true)
+}
+
+/// # disable_raid_mode
+/// Turns off raid mode, and puts the verification level back to what it was
+/// before if it was raised. Returns `false` if raid mode wasn't on.
+pub async fn disable_raid_mode(
+    ctx: &Context,
+    mut guild: GuildId,
+    by: UserId,
+) -> CommandResult<bool> {
+    let prev = {
+        let botdata = ctx.data.read().await;
+        let conn = match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn,
+            None => return Ok(false),
+        };
+        let conf = dbx::get_antiraid(conn, guild.0).await?;
+        if !conf.active {
+            return Ok(false);
+        }
+        dbx::set_raid_mode(conn, guild.0, false, 0).await?;
+        level_from(conf.prev_level)
+    };
+    if prev < VerificationLevel::High {
+        guild.edit(ctx, |g| g.verification_level(prev)).await?;
+    }
+    alert(
+        ctx,
+        guild,
+        "Raid mode disabled",
+        &format!(
+            "<@{}> turned raid mode off, new members can join again.",
+            by.0
+        ),
+    )
+    .await;
+    Ok(true)
+}
@@ -0,0 +1,210 @@
+use chrono::Utc;
+use serenity::{model::prelude::*, prelude::*};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use crate::{
+    automod::{enforce, is_exempt},
+    dbx::{self, AntiSpam, ZweiDbConn},
+    fmt_duration,
+};
+
+/// How many members' recent messages are kept per guild before the stale
+/// ones get cleaned up.
+const MAX_TRACKED: usize = 1000;
+
+/// # Sent
+/// A message someone sent recently, as far as anti-spam cares.
+pub struct Sent {
+    at: i64,
+    hash: u64,
+    channel: ChannelId,
+    id: MessageId,
+}
+
+/// # ZweiSpam
+/// A `TypeMapKey` holding every member's recent messages per guild. Only the
+/// messages within the guild's window are kept, so this never holds more than
+/// a few seconds of chat.
+pub struct ZweiSpam;
+impl TypeMapKey for ZweiSpam {
+    type Value = HashMap<u64, HashMap<u64, VecDeque<Sent>>>;
+}
+
+/// # ZweiAntiSpam
+/// A `TypeMapKey` caching each guild's anti-spam settings and exempt channels,
+/// so they aren't looked up for every message. Entries are dropped with
+/// [`invalidate`] whenever a guild changes its settings.
+pub struct ZweiAntiSpam;
+impl TypeMapKey for ZweiAntiSpam {
+    type Value = HashMap<u64, Arc<GuildAntiSpam>>;
+}
+
+/// # GuildAntiSpam
+/// Everything anti-spam needs to know about a guild.
+pub struct GuildAntiSpam {
+    pub conf: AntiSpam,
+    pub exempt: Vec<ChannelId>,
+}
+
+impl GuildAntiSpam {
+    /// # enabled
+    /// Whether any of the checks are turned on.
+    pub fn enabled(&self) -> bool {
+        self.conf.max_msgs > 0 || self.conf.max_dupes > 0 || self.conf.max_mentions > 0
+    }
+}
+
+/// # invalidate
+/// Drops the cached settings for a guild, so they're reloaded from the
+/// database on the next message. Call this after changing a guild's settings.
+pub async fn invalidate(ctx: &Context, guild: GuildId) {
+    if let Some(cache) = ctx.data.write().await.get_mut::<ZweiAntiSpam>() {
+        cache.remove(&guild.0);
+    }
+}
+
+/// # settings_for
+/// Gets a guild's anti-spam settings, loading them if they aren't cached yet.
+pub async fn settings_for(ctx: &Context, guild: GuildId) -> Option<Arc<GuildAntiSpam>> {
+    if let Some(cached) = ctx
+        .data
+        .read()
+        .await
+        .get::<ZweiAntiSpam>()
+        .and_then(|cache| cache.get(&guild.0))
+    {
+        return Some(cached.clone());
+    }
+    let loaded = {
+        let botdata = ctx.data.read().await;
+        let conn = botdata.get::<ZweiDbConn>()?;
+        let conf = dbx::get_antispam(conn, guild.0).await.ok()?;
+        let exempt = dbx::get_antispam_exempt(conn, guild.0).await.ok()?;
+        Arc::new(GuildAntiSpam {
+            conf,
+            exempt: exempt.into_iter().map(ChannelId).collect(),
+        })
+    };
+    ctx.data
+        .write()
+        .await
+        .entry::<ZweiAntiSpam>()
+        .or_default()
+        .insert(guild.0, loaded.clone());
+    Some(loaded)
+}
+
+/// # content_hash
+/// Hashes a message's content so duplicates can be spotted without keeping
+/// the text around. Case and surrounding whitespace don't matter.
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.trim().to_lowercase().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// # mention_count
+/// Counts the users and roles a message pings, with `@everyone` and `@here`
+/// counting as one.
+fn mention_count(msg: &Message) -> usize {
+    msg.mentions.len() + msg.mention_roles.len() + msg.mention_everyone as usize
+}
+
+/// # check_message
+/// Tracks a message in its author's window and acts on it if they're flooding
+/// the chat, repeating themselves or pinging too many people. Called from the
+/// `message` event for every message automod let through.
+pub async fn check_message(ctx: &Context, msg: &Message) {
+    let guild = match msg.guild_id {
+        Some(guild) => guild,
+        None => return,
+    };
+    if msg.author.bot || msg.webhook_id.is_some() {
+        return;
+    }
+    let spam = match settings_for(ctx, guild).await {
+        Some(spam) if spam.enabled() && !spam.exempt.contains(&msg.channel_id) => spam,
+        _ => return,
+    };
+    let conf = &spam.conf;
+
+    let now = Utc::now().timestamp_millis();
+    let window = conf.per_secs * 1000;
+    let hash = content_hash(&msg.content);
+    let (reason, earlier) = {
+        let mut botdata = ctx.data.write().await;
+        let tracked = botdata
+            .entry::<ZweiSpam>()
+            .or_default()
+            .entry(guild.0)
+            .or_default();
+        if tracked.len() > MAX_TRACKED {
+            tracked.retain(|_, sent| sent.back().is_some_and(|s| s.at > now - window));
+        }
+        let sent = tracked.entry(msg.author.id.0).or_default();
+        sent.push_back(Sent {
+            at: now,
+            hash,
+            channel: msg.channel_id,
+            id: msg.id,
+        });
+        while sent.front().is_some_and(|s| s.at <= now - window) {
+            sent.pop_front();
+        }
+
+        let dupes = match msg.content.is_empty() {
+            true => 0,
+            false => sent.iter().filter(|s| s.hash == hash).count(),
+        };
+        let mentions = mention_count(msg);
+        let within = fmt_duration(conf.per_secs as u64);
+        let reason = if conf.max_mentions > 0 && mentions as i64 >= conf.max_mentions {
+            Some(format!("Anti-spam: {mentions} mentions in one message"))
+        } else if conf.max_dupes > 0 && dupes as i64 >= conf.max_dupes {
+            Some(format!(
+                "Anti-spam: the same message {dupes} times within {within}"
+            ))
+        } else if conf.max_msgs > 0 && sent.len() as i64 >= conf.max_msgs {
+            Some(format!(
+                "Anti-spam: {} messages within {within}",
+                sent.len()
+            ))
+        } else {
+            None
+        };
+        match reason {
+            // Start over, so the next message doesn't trip it again.
+            Some(reason) => (reason, std::mem::take(sent)),
+            None => return,
+        }
+    };
+
+    if is_exempt(ctx, msg, &[]).await {
+        return;
+    }
+    if conf.action != "log" {
+        // `enforce` takes care of the message that tripped it, the rest of the
+        // flood goes here.
+        let mut by_channel: HashMap<ChannelId, Vec<MessageId>> = HashMap::new();
+        for s in earlier.iter().filter(|s| s.id != msg.id) {
+            by_channel.entry(s.channel).or_default().push(s.id);
+        }
+        for (channel, ids) in by_channel {
+            let res = match ids.as_slice() {
+                [id] => channel.delete_message(ctx, id).await,
+                _ => channel.delete_messages(ctx, ids).await,
+            };
+            if let Err(e) = res {
+                log::warn!(
+                    "Anti-spam couldn't clean up in channel {}\n\t{e}",
+                    channel.0
+                );
+            }
+        }
+    }
+    enforce(ctx, msg, &conf.action, conf.duration as u64, &reason).await;
+}
@@ -0,0 +1,260 @@
+use serenity::{
+    builder::CreateEmbed,
+    framework::standard::CommandResult,
+    http::StatusCode,
+    model::{
+        application::{
+            component::ButtonStyle,
+            interaction::{
+                message_component::MessageComponentInteraction, InteractionResponseType,
+            },
+        },
+        prelude::*,
+    },
+    prelude::*,
+    utils::Color,
+    Error,
+};
+
+use crate::{
+    commands::modtools::unban_member,
+    dbx::{self, Appeal, ZweiDbConn},
+    get_color,
+    modlog::log_case,
+    try_dm, zwei_conf,
+};
+
+/// How long someone has to wait between submitting appeals to the same
+/// server, in seconds.
+pub const APPEAL_COOLDOWN: i64 = 3600;
+/// The longest an appeal may be. Leaves room in the embed field for the rest.
+pub const MAX_APPEAL: usize = 1000;
+
+/// # open_appeal
+/// Opens a pending appeal for a ban if the guild takes appeals, returning the
+/// ID the user can submit it with. Called by `ban_member` before the ban
+/// notice is sent.
+pub async fn open_appeal(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    reason: &str,
+) -> Option<i64> {
+    let botdata = ctx.data.read().await;
+    let conn = botdata.get::<ZweiDbConn>()?;
+    match dbx::get_appeal_channel(conn, guild.0).await {
+        Ok(0) | Err(_) => None,
+        Ok(_) => dbx::add_appeal(conn, guild.0, target.0, reason).await.ok(),
+    }
+}
+
+/// # drop_appeal
+/// Removes an appeal opened for a ban that didn't go through.
+pub async fn drop_appeal(ctx: &Context, appealid: i64) {
+    let botdata = ctx.data.read().await;
+    if let Some(conn) = botdata.get::<ZweiDbConn>() {
+        let _ = dbx::remove_appeal(conn, appealid).await;
+    }
+}
+
+/// # appeal_embed
+/// Fills in an embed showing an appeal. Decided appeals say who decided.
+pub fn appeal_embed<'a>(
+    e: &'a mut CreateEmbed,
+    appeal: &Appeal,
+    color: Color,
+) -> &'a mut CreateEmbed {
+    e.color(color)
+        .title(format!("Ban appeal #{}", appeal.appealid))
+        .field("User", format!("<@{0}> (`{0}`)", appeal.userid), true)
+        .field("Submitted", format!("<t:{}:R>", appeal.submitted), true)
+        .field(
+            "Banned for",
+            if appeal.reason.is_empty() {
+                "_No reason given._"
+            } else {
+                &appeal.reason
+            },
+            false,
+        )
+        .field("Appeal", &appeal.message, false);
+    match appeal.status.as_str() {
+        "accepted" => e.field("Accepted", format!("by <@{}>", appeal.moderator), false),
+        "denied" => e.field("Denied", format!("by <@{}>", appeal.moderator), false),
+        _ => e,
+    }
+}
+
+/// # post_appeal
+/// Posts a freshly submitted appeal to the guild's appeals channel, with
+/// buttons for staff to accept or deny it. Only fails if the appeal didn't
+/// make it to the channel.
+pub async fn post_appeal(ctx: &Context, appeal: &Appeal) -> CommandResult {
+    let (channel, conn) = {
+        let botdata = ctx.data.read().await;
+        let conn = match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn.clone(),
+            None => return Err("no database connection".into()),
+        };
+        (
+            dbx::get_appeal_channel(&conn, appeal.serverid as u64).await?,
+            conn,
+        )
+    };
+    if channel == 0 {
+        return Err("this server doesn't take appeals anymore".into());
+    }
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    let posted = ChannelId(channel)
+        .send_message(ctx, |m| {
+            m.embed(|e| appeal_embed(e, appeal, color)).components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.custom_id(format!("appeal:accept:{}", appeal.appealid))
+                            .label("Accept")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("appeal:deny:{}", appeal.appealid))
+                            .label("Deny")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await?;
+    if let Err(e) = dbx::set_appeal_logmsg(&conn, appeal.appealid, posted.id.0).await {
+        log::warn!(
+            "Couldn't store the message for appeal {}\n\t{e}",
+            appeal.appealid
+        );
+    }
+    Ok(())
+}
+
+/// # respond_ephemeral
+/// Tells whoever pressed a button something only they can see.
+async fn respond_ephemeral(ctx: &Context, press: &MessageComponentInteraction, text: &str) {
+    if let Err(e) = press
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(text).ephemeral(true))
+        })
+        .await
+    {
+        log::warn!("Couldn't respond to a button press\n\t{e}");
+    }
+}
+
+/// # on_button
+/// Handles the Accept and Deny buttons on appeals. Only members who can ban
+/// may use them. Accepting lifts the ban, denying lets the user know. An
+/// appeal whose ban can't be lifted stays open, buttons and all.
+/// Buttons keep working after restarts, as everything needed is in the
+/// database.
+pub async fn on_button(ctx: &Context, press: &MessageComponentInteraction) {
+    let mut parts = press.data.custom_id.split(':').skip(1);
+    let (decision, appealid) = match (
+        parts.next(),
+        parts.next().and_then(|id| id.parse::<i64>().ok()),
+    ) {
+        (Some(decision @ ("accept" | "deny")), Some(id)) => (decision, id),
+        _ => return,
+    };
+    let allowed = press
+        .member
+        .as_ref()
+        .and_then(|m| m.permissions)
+        .is_some_and(|perms| perms.ban_members());
+    if !allowed {
+        respond_ephemeral(
+            ctx,
+            press,
+            "You need to be able to ban members to decide on appeals.",
+        )
+        .await;
+        return;
+    }
+
+    let moderator = press.user.id;
+    let status = match decision {
+        "accept" => "accepted",
+        _ => "denied",
+    };
+    let conn = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn.clone(),
+            None => {
+                return respond_ephemeral(ctx, press, "I can't reach my notes right now.").await
+            }
+        }
+    };
+    let appeal = match dbx::get_appeal(&conn, appealid).await {
+        Ok(Some(appeal)) if appeal.status == "open" => appeal,
+        Ok(_) => {
+            return respond_ephemeral(ctx, press, "Someone already decided on this appeal.").await
+        }
+        Err(_) => return respond_ephemeral(ctx, press, "I couldn't look up this appeal.").await,
+    };
+
+    // The ban has to be lifted before the appeal counts as accepted, so a
+    // failed unban leaves it open to try again.
+    let guild = GuildId(appeal.serverid as u64);
+    let target = UserId(appeal.userid as u64);
+    let reason = format!("Appeal #{appealid} accepted");
+    if status == "accepted" {
+        match unban_member(ctx, guild, target, &reason).await {
+            Ok(_) => {
+                log_case(ctx, guild, moderator, target.0, "unban", &reason).await;
+            }
+            // Someone already lifted the ban, which is all accepting has to do.
+            Err(Error::Http(e)) if e.status_code() == Some(StatusCode::NOT_FOUND) => (),
+            Err(e) => {
+                log::warn!("Couldn't lift the ban for appeal {appealid}\n\t{e}");
+                return respond_ephemeral(
+                    ctx,
+                    press,
+                    "I couldn't lift the ban, so the appeal is still open. Please try again, or unban them yourself.",
+                )
+                .await;
+            }
+        }
+    }
+    let appeal = match dbx::decide_appeal(&conn, appealid, status, moderator.0).await {
+        Ok(0) => {
+            return respond_ephemeral(ctx, press, "Someone already decided on this appeal.").await
+        }
+        Ok(_) => match dbx::get_appeal(&conn, appealid).await {
+            Ok(Some(appeal)) => appeal,
+            _ => return respond_ephemeral(ctx, press, "I couldn't look up this appeal.").await,
+        },
+        Err(_) => return respond_ephemeral(ctx, press, "I couldn't store the decision.").await,
+    };
+    if status == "denied" {
+        let guildname = guild
+            .name(ctx)
+            .unwrap_or_else(|| String::from("Unknown Server"));
+        let _ = try_dm(
+            ctx,
+            target,
+            "Appeal denied",
+            format!("Your appeal for your ban from {guildname} was denied."),
+        )
+        .await;
+    }
+
+    let color = get_color(&zwei_conf::CONF.ok_color).unwrap_or_default();
+    if let Err(e) = press
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| appeal_embed(e, &appeal, color))
+                        .components(|c| c)
+                })
+        })
+        .await
+    {
+        log::warn!("Couldn't update the message for appeal {appealid}\n\t{e}");
+    }
+}
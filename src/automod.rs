@@ -0,0 +1,356 @@
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serenity::{model::prelude::*, prelude::*};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    commands::modtools::{escalate, timeout_member, warn_member, MAX_TIMEOUT},
+    dbx::{self, AutomodRule, ZweiDbConn},
+    fmt_duration,
+    modlog::{alert, log_case},
+    sanitize_txt,
+};
+
+/// The kinds of rules automod knows about.
+pub const RULE_KINDS: [&str; 5] = ["word", "regex", "zalgo", "caps", "invite"];
+/// What automod can do when a rule matches.
+pub const RULE_ACTIONS: [&str; 4] = ["delete", "warn", "mute", "log"];
+/// How long automod mutes people for if the rule doesn't say.
+pub const DEFAULT_MUTE: u64 = 600;
+
+/// Discord invite links, including the older discordapp.com form.
+static INVITE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)(discord\.gg|discord(app)?\.com/invite)/[a-z0-9-]+").unwrap());
+
+/// # ZweiAutomod
+/// A `TypeMapKey` caching each guild's compiled automod rules and exempt
+/// roles, so regexes aren't rebuilt for every message. Entries are dropped
+/// with [`invalidate`] whenever a guild changes its rules.
+pub struct ZweiAutomod;
+impl TypeMapKey for ZweiAutomod {
+    type Value = HashMap<u64, Arc<GuildAutomod>>;
+}
+
+/// # GuildAutomod
+/// Everything automod needs to know about a guild.
+pub struct GuildAutomod {
+    pub rules: Vec<Rule>,
+    pub exempt: Vec<RoleId>,
+}
+
+/// # Rule
+/// An automod rule, ready to be matched against messages.
+pub struct Rule {
+    pub id: i64,
+    pub kind: String,
+    pub pattern: String,
+    pub action: String,
+    pub duration: u64,
+    matcher: Option<Regex>,
+}
+
+impl Rule {
+    /// # compile
+    /// Prepares a stored rule for matching. Returns `None` for rules whose
+    /// pattern doesn't compile anymore, which are skipped.
+    pub fn compile(rule: &AutomodRule) -> Option<Self> {
+        let matcher = match rule.kind.as_str() {
+            "word" | "regex" => match compile_pattern(&rule.kind, &rule.pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    log::warn!("Skipping automod rule {}\n\t{e}", rule.ruleid);
+                    return None;
+                }
+            },
+            _ => None,
+        };
+        Some(Self {
+            id: rule.ruleid,
+            kind: rule.kind.clone(),
+            pattern: rule.pattern.clone(),
+            action: rule.action.clone(),
+            duration: rule.duration as u64,
+            matcher,
+        })
+    }
+
+    /// # matches
+    /// Checks whether a message's content breaks this rule.
+    pub fn matches(&self, content: &str) -> bool {
+        match self.kind.as_str() {
+            "zalgo" => is_zalgo(content),
+            "caps" => is_shouting(content),
+            "invite" => INVITE.is_match(content),
+            _ => self.matcher.as_ref().is_some_and(|re| re.is_match(content)),
+        }
+    }
+
+    /// # describe
+    /// A short description of the rule for listings and reasons.
+    pub fn describe(&self) -> String {
+        let what = match self.kind.as_str() {
+            "word" => format!("the word `{}`", self.pattern.replace('`', "'")),
+            "regex" => format!("the pattern `{}`", self.pattern.replace('`', "'")),
+            "zalgo" => "zalgo text".to_owned(),
+            "caps" => "excessive caps".to_owned(),
+            "invite" => "invite links".to_owned(),
+            other => other.to_owned(),
+        };
+        match self.action.as_str() {
+            "mute" => format!("{what}, mute for {}", fmt_duration(self.duration)),
+            action => format!("{what}, {action}"),
+        }
+    }
+}
+
+/// # compile_pattern
+/// Builds the regex for a word or regex rule. Words only match as a whole
+/// word and ignore case, regexes are used as given.
+pub fn compile_pattern(kind: &str, pattern: &str) -> Result<Regex, regex::Error> {
+    let pattern = match kind {
+        "word" => format!(r"(?i)\b{}\b", regex::escape(pattern)),
+        _ => pattern.to_owned(),
+    };
+    RegexBuilder::new(&pattern).size_limit(1 << 16).build()
+}
+
+/// # is_zalgo
+/// Zalgo text piles combining marks on top of letters. A few in a row are
+/// fine for some languages, but a stack of them isn't.
+pub(crate) fn is_zalgo(content: &str) -> bool {
+    let mut run = 0;
+    for c in content.chars() {
+        let combining = matches!(c as u32,
+            0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F);
+        if combining {
+            run += 1;
+            if run >= 4 {
+                return true;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    false
+}
+
+/// # is_shouting
+/// Checks for messages mostly written in capitals. Short messages don't
+/// count, so a single "OK" or "LOL" doesn't set anything off.
+fn is_shouting(content: &str) -> bool {
+    let letters = content.chars().filter(|c| c.is_alphabetic());
+    let (total, upper) = letters.fold((0, 0), |(t, u), c| (t + 1, u + c.is_uppercase() as usize));
+    total >= 10 && upper * 100 >= total * 70
+}
+
+/// # invalidate
+/// Drops the cached rules for a guild, so they're reloaded from the database
+/// on the next message. Call this after changing a guild's rules.
+pub async fn invalidate(ctx: &Context, guild: GuildId) {
+    if let Some(cache) = ctx.data.write().await.get_mut::<ZweiAutomod>() {
+        cache.remove(&guild.0);
+    }
+}
+
+/// # rules_for
+/// Gets a guild's automod rules, loading and compiling them if they aren't
+/// cached yet.
+pub async fn rules_for(ctx: &Context, guild: GuildId) -> Option<Arc<GuildAutomod>> {
+    if let Some(cached) = ctx
+        .data
+        .read()
+        .await
+        .get::<ZweiAutomod>()
+        .and_then(|cache| cache.get(&guild.0))
+    {
+        return Some(cached.clone());
+    }
+    let loaded = {
+        let botdata = ctx.data.read().await;
+        let conn = botdata.get::<ZweiDbConn>()?;
+        let rules = dbx::get_automod_rules(conn, guild.0).await.ok()?;
+        let exempt = dbx::get_automod_exempt(conn, guild.0).await.ok()?;
+        Arc::new(GuildAutomod {
+            rules: rules.iter().filter_map(Rule::compile).collect(),
+            exempt: exempt.into_iter().map(RoleId).collect(),
+        })
+    };
+    ctx.data
+        .write()
+        .await
+        .entry::<ZweiAutomod>()
+        .or_default()
+        .insert(guild.0, loaded.clone());
+    Some(loaded)
+}
+
+/// # is_exempt
+/// Moderators (anyone who can manage messages) and members with an exempt
+/// role are left alone by automatic moderation. Members that can't be looked
+/// up are left alone too, as they might well be moderators.
+pub async fn is_exempt(ctx: &Context, msg: &Message, exempt: &[RoleId]) -> bool {
+    let member = match msg.member(ctx).await {
+        Ok(member) => member,
+        Err(e) => {
+            log::warn!(
+                "Couldn't look up {} for automatic moderation, skipping them\n\t{e}",
+                msg.author.id.0
+            );
+            return true;
+        }
+    };
+    if member.roles.iter().any(|r| exempt.contains(r)) {
+        return true;
+    }
+    member
+        .permissions(ctx)
+        .map_or(true, |perms| perms.manage_messages())
+}
+
+/// # check_message
+/// Runs a message past its guild's automod rules, acting on the first rule
+/// it breaks. Called from the `message` event for every message. Returns
+/// whether automod acted on it.
+pub async fn check_message(ctx: &Context, msg: &Message) -> bool {
+    let guild = match msg.guild_id {
+        Some(guild) => guild,
+        None => return false,
+    };
+    if msg.author.bot || msg.webhook_id.is_some() || msg.content.is_empty() {
+        return false;
+    }
+    let conf = match rules_for(ctx, guild).await {
+        Some(conf) if !conf.rules.is_empty() => conf,
+        _ => return false,
+    };
+    let rule = match conf.rules.iter().find(|r| r.matches(&msg.content)) {
+        Some(rule) => rule,
+        None => return false,
+    };
+    if is_exempt(ctx, msg, &conf.exempt).await {
+        return false;
+    }
+    let reason = format!("Automod rule #{}: {}", rule.id, rule.describe());
+    enforce(ctx, msg, &rule.action, rule.duration, &reason).await;
+    true
+}
+
+/// # enforce
+/// Takes an automatic moderation action against the author of a message.
+/// Everything but `log` deletes the message, `warn` and `mute` also punish
+/// the author. Shared by automod and anti-spam.
+pub async fn enforce(ctx: &Context, msg: &Message, action: &str, duration: u64, reason: &str) {
+    let guild = msg.guild_id.unwrap_or_default();
+    let target = msg.author.id;
+    let me = ctx.cache.current_user_id();
+    if action != "log" {
+        if let Err(e) = msg.delete(ctx).await {
+            log::warn!("Automod couldn't delete message {}\n\t{e}", msg.id.0);
+        }
+    }
+    match action {
+        "warn" => match warn_member(ctx, guild, target, me, reason).await {
+            Ok((count, Some(rule))) => {
+                if let Err(e) = escalate(ctx, guild, target, count, &rule).await {
+                    log::warn!("Automod couldn't escalate for {}\n\t{e}", target.0);
+                }
+            }
+            Ok(_) => (),
+            Err(e) => log::error!("Automod couldn't warn {}\n\t{e}", target.0),
+        },
+        "mute" => {
+            let secs = duration.clamp(1, MAX_TIMEOUT);
+            match timeout_member(ctx, guild, target, secs, reason).await {
+                Ok(()) => {
+                    let reason = format!("{reason} (for {})", fmt_duration(secs));
+                    log_case(ctx, guild, me, target.0, "mute", &reason).await;
+                }
+                Err(e) => log::warn!("Automod couldn't mute {}\n\t{e}", target.0),
+            }
+        }
+        _ => {
+            let mut excerpt: String = msg.content.chars().take(500).collect();
+            if excerpt.len() < msg.content.len() {
+                excerpt.push_str("...");
+            }
+            let title = match action {
+                "log" => "Automod flagged a message",
+                _ => "Automod deleted a message",
+            };
+            alert(
+                ctx,
+                guild,
+                title,
+                &format!(
+                    "<@{}> in <#{}>\n{reason}\n>>> {}",
+                    target.0,
+                    msg.channel_id.0,
+                    sanitize_txt(&excerpt)
+                ),
+            )
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zalgo_needs_a_stack_of_marks() {
+        assert!(!is_zalgo(""));
+        assert!(!is_zalgo("plain text"));
+        assert!(!is_zalgo("cafe\u{301}"));
+        assert!(!is_zalgo("a\u{300}\u{301}\u{302}"));
+        assert!(is_zalgo("a\u{300}\u{301}\u{302}\u{303}"));
+        assert!(is_zalgo("h\u{1AB0}\u{1DC0}\u{20D0}\u{FE20}i"));
+    }
+
+    #[test]
+    fn zalgo_marks_have_to_be_in_a_row() {
+        assert!(!is_zalgo("a\u{300}\u{301}b\u{302}\u{303}c\u{304}"));
+    }
+
+    #[test]
+    fn shouting_needs_enough_letters() {
+        assert!(!is_shouting(""));
+        assert!(!is_shouting("LOL"));
+        assert!(!is_shouting("ABCDEFGHI!!!!!!!!"));
+        assert!(is_shouting("ABCDEFGHIJ"));
+    }
+
+    #[test]
+    fn shouting_starts_at_seventy_percent() {
+        assert!(is_shouting("ABCDEFGhij"));
+        assert!(!is_shouting("ABCDEFghij"));
+        assert!(is_shouting("ABCDEFGhij 1234567890 !?"));
+        assert!(is_shouting("ÄÖÜÉÈÀÇäöü"));
+    }
+
+    #[test]
+    fn word_patterns_match_whole_words() {
+        let re = compile_pattern("word", "bad").unwrap();
+        assert!(re.is_match("that's BAD!"));
+        assert!(re.is_match("bad"));
+        assert!(!re.is_match("badge"));
+        assert!(!re.is_match(""));
+    }
+
+    #[test]
+    fn word_patterns_are_literal() {
+        let re = compile_pattern("word", "a.b").unwrap();
+        assert!(re.is_match("a.b"));
+        assert!(!re.is_match("axb"));
+        assert!(compile_pattern("word", "(").is_ok());
+    }
+
+    #[test]
+    fn regex_patterns_are_checked() {
+        assert!(compile_pattern("regex", r"free\s+nitro")
+            .unwrap()
+            .is_match("free  nitro"));
+        assert!(compile_pattern("regex", "(").is_err());
+        assert!(compile_pattern("regex", r"\w{1000}").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use crate::{
+    antiraid::{disable_raid_mode, enable_raid_mode},
+    db_conn, dbx, fmt_duration, parse_duration, send_err, send_err_titled, send_ok,
+};
+
+#[command("status")]
+#[aliases("show")]
+#[required_permissions("MANAGE_GUILD")]
+#[max_args(0)]
+#[description = "Shows the current anti-raid settings and whether raid mode is on."]
+async fn raid_status(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let conf = {
+        let conn = &match db_conn(ctx, msg, "show anti-raid settings").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_antiraid(conn, guild_id).await?
+    };
+    let mode = if conf.active {
+        "**Raid mode is on.** I'm kicking everyone who joins."
+    } else {
+        "Raid mode is off."
+    };
+    let threshold = match conf.joins {
+        0 => "I'm not watching how fast people join.".to_owned(),
+        n => format!(
+            "I'll turn on raid mode when {n} members join within {}.",
+            fmt_duration(conf.per_secs as u64)
+        ),
+    };
+    let age = match conf.min_age {
+        0 => "I'm not flagging new accounts.".to_owned(),
+        secs => format!(
+            "I'll flag accounts younger than {} in the mod-log.",
+            fmt_duration(secs as u64)
+        ),
+    };
+    send_ok(
+        ctx,
+        msg,
+        "Anti-raid settings",
+        format!("{mode}\n{threshold}\n{age}"),
+    )
+    .await
+}
+
+#[command("threshold")]
+#[aliases("joins")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(1)]
+#[max_args(2)]
+#[description = "Turns on raid mode when this many members join within the given time (10 seconds if left out). Use 0 or `off` to stop watching joins."]
+#[example = "10 30s"]
+#[example = "off"]
+async fn raid_threshold(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let joins = match args.current() {
+        Some(off) if off.eq_ignore_ascii_case("off") => 0,
+        _ => match args.parse::<u32>() {
+            Ok(n) => n,
+            Err(_) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "No threshold provided!",
+                    "Please tell me how many joins should turn on raid mode.",
+                )
+                .await;
+            }
+        },
+    };
+    args.advance();
+    let per_secs = match args.current() {
+        Some(txt) => match parse_duration(txt) {
+            Some(secs) => secs,
+            None => {
+                return send_err(
+                    ctx,
+                    msg,
+                    "That's not a time I understand. Try something like `30s` or `2m`.",
+                )
+                .await;
+            }
+        },
+        None => 10,
+    };
+    let guild_id = msg.guild_id.unwrap().0;
+    {
+        let conn = &match db_conn(ctx, msg, "set the raid threshold").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::set_raid_threshold(conn, guild_id, joins as i64, per_secs as i64).await?;
+    }
+    match joins {
+        0 => {
+            send_ok(
+                ctx,
+                msg,
+                "Raid threshold removed",
+                "I'll stop watching how fast people join.",
+            )
+            .await
+        }
+        n => {
+            send_ok(
+                ctx,
+                msg,
+                "Raid threshold set",
+                format!(
+                    "I'll turn on raid mode when {n} members join within {}.",
+                    fmt_duration(per_secs)
+                ),
+            )
+            .await
+        }
+    }
+}
+
+#[command("accountage")]
+#[aliases("age", "minage")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Flags accounts younger than this in the mod-log when they join. Use `off` to stop flagging them."]
+#[example = "7d"]
+#[example = "off"]
+async fn raid_account_age(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let secs = match args.current() {
+        Some(off) if off.eq_ignore_ascii_case("off") => 0,
+        Some(txt) => match parse_duration(txt) {
+            Some(secs) => secs,
+            None => {
+                return send_err(
+                    ctx,
+                    msg,
+                    "That's not an age I understand. Try something like `3d` or `1w`.",
+                )
+                .await;
+            }
+        },
+        None => 0,
+    };
+    let guild_id = msg.guild_id.unwrap().0;
+    {
+        let conn = &match db_conn(ctx, msg, "set the account age").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::set_raid_min_age(conn, guild_id, secs as i64).await?;
+    }
+    match secs {
+        0 => send_ok(ctx, msg, "New accounts", "I'll stop flagging new accounts.").await,
+        secs => {
+            send_ok(
+                ctx,
+                msg,
+                "New accounts",
+                format!(
+                    "I'll flag accounts younger than {} in the mod-log.",
+                    fmt_duration(secs)
+                ),
+            )
+            .await
+        }
+    }
+}
+
+#[command("on")]
+#[aliases("enable", "lockdown")]
+#[required_permissions("MANAGE_GUILD")]
+#[description = "Turns on raid mode right away. I'll raise the verification level and kick everyone who joins until it's turned off."]
+async fn raid_on(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    let why = format!("<@{}> turned on raid mode", msg.author.id.0);
+    match enable_raid_mode(ctx, guild, &why).await {
+        Ok(true) => {
+            send_ok(
+                ctx,
+                msg,
+                "Raid mode enabled",
+                "I'll kick everyone who joins until you turn it off with `antiraid off`.",
+            )
+            .await
+        }
+        Ok(false) => send_err(ctx, msg, "Raid mode is already on.").await,
+        Err(e) => {
+            log::error!("Couldn't enable raid mode in {}\n\t{e}", guild.0);
+            send_err(ctx, msg, "I couldn't turn on raid mode.").await
+        }
+    }
+}
+
+#[command("off")]
+#[aliases("disable")]
+#[required_permissions("MANAGE_GUILD")]
+#[description = "Turns off raid mode and puts the verification level back to what it was."]
+async fn raid_off(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    match disable_raid_mode(ctx, guild, msg.author.id).await {
+        Ok(true) => {
+            send_ok(
+                ctx,
+                msg,
+                "Raid mode disabled",
+                "New members can join again.",
+            )
+            .await
+        }
+        Ok(false) => send_err(ctx, msg, "Raid mode isn't on.").await,
+        Err(e) => {
+            log::error!("Couldn't disable raid mode in {}\n\t{e}", guild.0);
+            send_err(
+                ctx,
+                msg,
+                "Something went wrong turning off raid mode. Please check the verification level yourself.",
+            )
+            .await
+        }
+    }
+}
+
+#[group("AntiRaid")]
+#[commands(raid_status, raid_threshold, raid_account_age, raid_on, raid_off)]
+#[summary = "Watches for raids and locks the server down when one happens. Shows the current settings if no extra arguments are given."]
+#[prefixes("antiraid", "raid")]
+#[default_command(raid_status)]
+#[only_in("guilds")]
+struct AntiRaid;
@@ -0,0 +1,324 @@
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use crate::{
+    antispam::{invalidate, settings_for},
+    automod::RULE_ACTIONS,
+    commands::modtools::MAX_TIMEOUT,
+    db_conn, dbx, fmt_duration, parse_duration, send_err, send_err_titled, send_ok,
+};
+
+/// The highest limit any of the checks accept. Anything above this isn't
+/// going to catch spam anyway, and floods get cleaned up in one bulk delete.
+const MAX_LIMIT: u32 = 50;
+/// The longest window messages are counted in.
+const MAX_WINDOW: u64 = 300;
+
+/// # describe
+/// Describes the anti-spam settings, one check per line.
+fn describe(conf: &dbx::AntiSpam) -> String {
+    let within = fmt_duration(conf.per_secs as u64);
+    let flood = match conf.max_msgs {
+        0 => "I'm not watching for message floods.".to_owned(),
+        n => format!("Sending {n} messages within {within} is a flood."),
+    };
+    let dupes = match conf.max_dupes {
+        0 => "I'm not watching for repeated messages.".to_owned(),
+        n => format!("Sending the same message {n} times within {within} is spam."),
+    };
+    let mentions = match conf.max_mentions {
+        0 => "I'm not watching for mass mentions.".to_owned(),
+        n => format!("Mentioning {n} users or roles in one message is spam."),
+    };
+    let action = match conf.action.as_str() {
+        "mute" => format!(
+            "Spammers get muted for {}.",
+            fmt_duration(conf.duration as u64)
+        ),
+        "warn" => "Spammers get their message deleted and a warning.".to_owned(),
+        "delete" => "Spammers get their messages deleted.".to_owned(),
+        _ => "Spam gets reported in the mod-log.".to_owned(),
+    };
+    format!("{flood}\n{dupes}\n{mentions}\n{action}")
+}
+
+/// # parse_limit
+/// Reads a limit for one of the checks, where `off` means 0.
+fn parse_limit(args: &mut Args) -> Option<u32> {
+    match args.current() {
+        Some(off) if off.eq_ignore_ascii_case("off") => Some(0),
+        _ => args.parse::<u32>().ok().filter(|n| *n <= MAX_LIMIT),
+    }
+}
+
+/// # update
+/// Changes this guild's anti-spam settings and shows the result. Shared by
+/// all commands that change a setting.
+async fn update<F>(ctx: &Context, msg: &Message, change: F) -> CommandResult
+where
+    F: FnOnce(&mut dbx::AntiSpam),
+{
+    let guild = msg.guild_id.unwrap();
+    let conf = {
+        let conn = &match db_conn(ctx, msg, "change anti-spam settings").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        let mut conf = dbx::get_antispam(conn, guild.0).await?;
+        change(&mut conf);
+        dbx::set_antispam(conn, guild.0, &conf).await?;
+        conf
+    };
+    invalidate(ctx, guild).await;
+    send_ok(ctx, msg, "Anti-spam settings updated", describe(&conf)).await
+}
+
+#[command("status")]
+#[aliases("show")]
+#[required_permissions("MANAGE_GUILD")]
+#[max_args(0)]
+#[description = "Shows the current anti-spam settings and the channels it leaves alone."]
+async fn spam_status(ctx: &Context, msg: &Message) -> CommandResult {
+    let spam = match settings_for(ctx, msg.guild_id.unwrap()).await {
+        Some(spam) => spam,
+        None => return send_err(ctx, msg, "I couldn't load the anti-spam settings.").await,
+    };
+    let mut text = describe(&spam.conf);
+    if !spam.exempt.is_empty() {
+        let channels: Vec<String> = spam.exempt.iter().map(|c| format!("<#{}>", c.0)).collect();
+        text.push_str(&format!(
+            "\n\nI'm leaving these channels alone: {}",
+            channels.join(", ")
+        ));
+    }
+    send_ok(ctx, msg, "Anti-spam settings", text).await
+}
+
+#[command("flood")]
+#[aliases("messages", "rate")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(1)]
+#[max_args(2)]
+#[description = "Treats this many messages from one member within the given time as a flood (5 seconds if left out). The time is also used for repeated messages. Use `off` to stop watching for floods."]
+#[example = "6 5s"]
+#[example = "off"]
+async fn spam_flood(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let count = match parse_limit(&mut args) {
+        Some(n) => n,
+        None => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No limit provided!",
+                format!("Please tell me how many messages count as a flood, up to {MAX_LIMIT}."),
+            )
+            .await;
+        }
+    };
+    args.advance();
+    let per_secs = match args.current() {
+        Some(txt) => match parse_duration(txt) {
+            Some(secs) if secs > 0 && secs <= MAX_WINDOW => secs,
+            _ => {
+                return send_err(
+                    ctx,
+                    msg,
+                    "That's not a time I can use. Try something between `1s` and `5m`.",
+                )
+                .await;
+            }
+        },
+        None => 5,
+    };
+    update(ctx, msg, |conf| {
+        conf.max_msgs = count as i64;
+        conf.per_secs = per_secs as i64;
+    })
+    .await
+}
+
+#[command("duplicates")]
+#[aliases("dupes", "repeats")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Treats the same message sent this many times by one member as spam. Use `off` to allow repeating messages."]
+#[example = "3"]
+#[example = "off"]
+async fn spam_duplicates(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let count = match parse_limit(&mut args) {
+        Some(n) => n,
+        None => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No limit provided!",
+                format!("Please tell me how many repeats count as spam, up to {MAX_LIMIT}."),
+            )
+            .await;
+        }
+    };
+    update(ctx, msg, |conf| conf.max_dupes = count as i64).await
+}
+
+#[command("mentions")]
+#[aliases("pings")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Treats a message mentioning this many users or roles as spam. Use `off` to allow any number of mentions."]
+#[example = "8"]
+#[example = "off"]
+async fn spam_mentions(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let count = match parse_limit(&mut args) {
+        Some(n) => n,
+        None => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No limit provided!",
+                format!("Please tell me how many mentions count as spam, up to {MAX_LIMIT}."),
+            )
+            .await;
+        }
+    };
+    update(ctx, msg, |conf| conf.max_mentions = count as i64).await
+}
+
+#[command("action")]
+#[aliases("punish")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(1)]
+#[max_args(2)]
+#[description = "Sets what happens to spammers: `delete`, `warn`, `mute` (optionally for a duration, 10 minutes by default) or `log`."]
+#[example = "mute 30m"]
+#[example = "warn"]
+async fn spam_action(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+    if !RULE_ACTIONS.contains(&action.as_str()) {
+        return send_err_titled(
+            ctx,
+            msg,
+            "Unknown action!",
+            format!("Actions can be one of `{}`.", RULE_ACTIONS.join("`, `")),
+        )
+        .await;
+    }
+    let duration = match args.current() {
+        Some(txt) if action == "mute" => match parse_duration(txt) {
+            Some(secs) if secs > 0 && secs <= MAX_TIMEOUT => Some(secs),
+            _ => {
+                return send_err(
+                    ctx,
+                    msg,
+                    "That's not a time I can mute people for. Try something between `1m` and `28d`.",
+                )
+                .await;
+            }
+        },
+        _ => None,
+    };
+    update(ctx, msg, |conf| {
+        conf.action = action;
+        if let Some(secs) = duration {
+            conf.duration = secs as i64;
+        }
+    })
+    .await
+}
+
+#[command("exempt")]
+#[aliases("allow")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Makes anti-spam leave this channel alone, like a counting or bot channel."]
+#[example = "#counting"]
+async fn spam_exempt(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    set_exempt(ctx, msg, &mut args, true).await
+}
+
+#[command("unexempt")]
+#[aliases("disallow")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Makes anti-spam check this channel again."]
+#[example = "#counting"]
+async fn spam_unexempt(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    set_exempt(ctx, msg, &mut args, false).await
+}
+
+/// # set_exempt
+/// Shared body of `exempt` and `unexempt`.
+async fn set_exempt(ctx: &Context, msg: &Message, args: &mut Args, exempt: bool) -> CommandResult {
+    args.trimmed();
+    let channel = match args.parse::<ChannelId>() {
+        Ok(channel) => channel,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No channel provided!",
+                "Please give me a channel mention or an ID.",
+            )
+            .await;
+        }
+    };
+    let guild = msg.guild_id.unwrap();
+    let changed = {
+        let conn = &match db_conn(ctx, msg, "change anti-spam exemptions").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        match exempt {
+            true => dbx::add_antispam_exempt(conn, guild.0, channel.0).await?,
+            false => dbx::remove_antispam_exempt(conn, guild.0, channel.0).await?,
+        }
+    };
+    invalidate(ctx, guild).await;
+    match (exempt, changed) {
+        (true, 0) => send_err(ctx, msg, format!("<#{}> was already exempt.", channel.0)).await,
+        (true, _) => {
+            send_ok(
+                ctx,
+                msg,
+                "Channel exempted",
+                format!("I'll leave <#{}> alone.", channel.0),
+            )
+            .await
+        }
+        (false, 0) => send_err(ctx, msg, format!("<#{}> wasn't exempt.", channel.0)).await,
+        (false, _) => {
+            send_ok(
+                ctx,
+                msg,
+                "Exemption removed",
+                format!("I'll watch <#{}> for spam again.", channel.0),
+            )
+            .await
+        }
+    }
+}
+
+#[group("AntiSpam")]
+#[commands(
+    spam_status,
+    spam_flood,
+    spam_duplicates,
+    spam_mentions,
+    spam_action,
+    spam_exempt,
+    spam_unexempt
+)]
+#[summary = "Acts on members who flood the chat, repeat themselves or mass mention. Moderators are left alone. Shows the current settings if no extra arguments are given."]
+#[prefixes("antispam", "spam")]
+#[default_command(spam_status)]
+#[only_in("guilds")]
+struct AntiSpam;
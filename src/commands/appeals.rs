@@ -0,0 +1,207 @@
+use chrono::Utc;
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use crate::{
+    appeals::{post_appeal, APPEAL_COOLDOWN, MAX_APPEAL},
+    db_conn,
+    dbx::{self, ZweiDbConn},
+    fmt_duration, send_err, send_err_titled, send_ok,
+};
+
+#[command]
+#[only_in("dm")]
+#[min_args(2)]
+#[description = "Appeals a ban, using the appeal ID I sent you along with it. Your message goes to the server's staff, and I'll let you know what they decide."]
+#[example = "12 I was hacked, the spam wasn't me. I've changed my password since."]
+async fn appeal(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let appealid = match args.single::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No appeal provided!",
+                "Please give me the appeal ID from the message I sent you when you were banned.",
+            )
+            .await;
+        }
+    };
+    let text = args.rest().trim();
+    if text.is_empty() {
+        return send_err(ctx, msg, "What would you like to tell the staff?").await;
+    }
+    if text.chars().count() > MAX_APPEAL {
+        return send_err(
+            ctx,
+            msg,
+            format!("Please keep your appeal under {MAX_APPEAL} characters."),
+        )
+        .await;
+    }
+
+    let appeal = {
+        let conn = &match db_conn(ctx, msg, "submit an appeal").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        let appeal = match dbx::get_appeal(conn, appealid).await? {
+            Some(a) if a.userid as u64 == msg.author.id.0 => a,
+            _ => {
+                return send_err(ctx, msg, format!("You have no appeal `#{appealid}`.")).await;
+            }
+        };
+        if appeal.status != "pending" {
+            return send_err(
+                ctx,
+                msg,
+                format!(
+                    "You already submitted appeal `#{appealid}`, the staff will get back to you."
+                ),
+            )
+            .await;
+        }
+        let wait = dbx::last_appeal(conn, appeal.serverid as u64, msg.author.id.0).await?
+            + APPEAL_COOLDOWN
+            - Utc::now().timestamp();
+        if wait > 0 {
+            return send_err(
+                ctx,
+                msg,
+                format!(
+                    "You recently appealed a ban from this server. Please try again in {}.",
+                    fmt_duration(wait as u64)
+                ),
+            )
+            .await;
+        }
+        if dbx::submit_appeal(conn, appealid, text).await? == 0 {
+            return send_err(
+                ctx,
+                msg,
+                format!("Appeal `#{appealid}` can't be submitted anymore."),
+            )
+            .await;
+        }
+        match dbx::get_appeal(conn, appealid).await? {
+            Some(a) => a,
+            None => return send_err(ctx, msg, "Your appeal disappeared, sorry.").await,
+        }
+    };
+
+    // An appeal nobody can see shouldn't count as submitted, so it goes back
+    // to pending and can be sent again.
+    if let Err(e) = post_appeal(ctx, &appeal).await {
+        log::error!("Failed to post appeal {appealid} for the staff\n\t{e}");
+        if let Some(conn) = ctx.data.read().await.get::<ZweiDbConn>() {
+            if let Err(e) = dbx::unsubmit_appeal(conn, appealid).await {
+                log::error!("Couldn't put appeal {appealid} back to pending\n\t{e}");
+            }
+        }
+        return send_err(
+            ctx,
+            msg,
+            "I couldn't pass your appeal on to the staff, so it wasn't submitted. Please try again later.",
+        )
+        .await;
+    }
+    send_ok(
+        ctx,
+        msg,
+        "Appeal submitted",
+        "I passed your appeal on to the staff. I'll let you know what they decide.",
+    )
+    .await
+}
+
+#[command]
+#[required_permissions("MANAGE_GUILD")]
+#[only_in("guilds")]
+#[max_args(1)]
+#[aliases("appealschannel")]
+#[description = "Sets the channel ban appeals are posted to. Banned members only get an appeal ID while one is set. Use `none` to stop taking appeals, or nothing to see the current one."]
+#[example = "#appeals"]
+#[example = "none"]
+async fn appealchannel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild_id = msg.guild_id.unwrap().0;
+    let channel = match args.current() {
+        None => None,
+        Some(c) if c.eq_ignore_ascii_case("none") => Some(0),
+        Some(_) => match args.parse::<ChannelId>() {
+            Ok(c) => Some(c.0),
+            Err(_) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Invalid channel!",
+                    "Please give me a channel mention or an ID.",
+                )
+                .await;
+            }
+        },
+    };
+    let current = {
+        let conn = &match db_conn(ctx, msg, "set the appeals channel").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        match channel {
+            Some(c) => {
+                dbx::set_appeal_channel(conn, guild_id, c).await?;
+                c
+            }
+            None => dbx::get_appeal_channel(conn, guild_id).await?,
+        }
+    };
+    match (channel, current) {
+        (None, 0) => {
+            send_ok(
+                ctx,
+                msg,
+                "Appeals",
+                "There's no appeals channel set, so bans can't be appealed.",
+            )
+            .await
+        }
+        (None, c) => {
+            send_ok(
+                ctx,
+                msg,
+                "Appeals",
+                format!("I'm posting ban appeals to <#{c}>."),
+            )
+            .await
+        }
+        (Some(_), 0) => {
+            send_ok(
+                ctx,
+                msg,
+                "Appeals disabled",
+                "New bans can't be appealed anymore.",
+            )
+            .await
+        }
+        (Some(_), c) => {
+            send_ok(
+                ctx,
+                msg,
+                "Appeals channel set",
+                format!("I'll post ban appeals to <#{c}>, with buttons to accept or deny them."),
+            )
+            .await
+        }
+    }
+}
+
+#[group("Appeals")]
+#[commands(appeal, appealchannel)]
+#[summary = "Lets banned users appeal their ban by DMing me. Appeals are posted for staff to accept or deny."]
+struct Appeals;
@@ -0,0 +1,311 @@
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use crate::{
+    automod::{compile_pattern, invalidate, rules_for, DEFAULT_MUTE, RULE_ACTIONS, RULE_KINDS},
+    commands::modtools::MAX_TIMEOUT,
+    db_conn, dbx, fit_embed, parse_duration, send_err, send_err_titled, send_ok,
+};
+
+#[command("add")]
+#[aliases("create", "+")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(2)]
+#[description = "Adds an automod rule. Kinds are `word` and `regex` (which need a pattern), `zalgo`, `caps` and `invite`. Actions are `delete`, `warn`, `mute` (optionally for a duration, 10 minutes by default) and `log`."]
+#[example = "word delete badword"]
+#[example = "regex mute 1h free\\s+nitro"]
+#[example = "invite warn"]
+async fn automod_add(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let kind = args.single::<String>().unwrap_or_default().to_lowercase();
+    if !RULE_KINDS.contains(&kind.as_str()) {
+        return send_err_titled(
+            ctx,
+            msg,
+            "Unknown rule kind!",
+            format!("Rules can be one of `{}`.", RULE_KINDS.join("`, `")),
+        )
+        .await;
+    }
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+    if !RULE_ACTIONS.contains(&action.as_str()) {
+        return send_err_titled(
+            ctx,
+            msg,
+            "Unknown action!",
+            format!("Actions can be one of `{}`.", RULE_ACTIONS.join("`, `")),
+        )
+        .await;
+    }
+    let mut duration = 0;
+    if action == "mute" {
+        duration = DEFAULT_MUTE;
+        if let Some(secs) = args.current().and_then(parse_duration) {
+            if secs > MAX_TIMEOUT {
+                return send_err(
+                    ctx,
+                    msg,
+                    "Automod can't mute people for longer than 28 days.",
+                )
+                .await;
+            }
+            duration = secs;
+            args.advance();
+        }
+    }
+    let pattern = match kind.as_str() {
+        "word" | "regex" => {
+            let pattern = args.rest().trim();
+            if pattern.is_empty() {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "No pattern provided!",
+                    format!("A `{kind}` rule needs something to look for."),
+                )
+                .await;
+            }
+            if compile_pattern(&kind, pattern).is_err() {
+                return send_err(ctx, msg, "That's not a pattern I can use.").await;
+            }
+            pattern
+        }
+        _ => "",
+    };
+
+    let guild = msg.guild_id.unwrap();
+    let ruleid = {
+        let conn = &match db_conn(ctx, msg, "add an automod rule").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::add_automod_rule(conn, guild.0, &kind, pattern, &action, duration as i64).await?
+    };
+    invalidate(ctx, guild).await;
+    send_ok(
+        ctx,
+        msg,
+        "Automod rule added",
+        format!("I'll keep an eye out for this as rule #{ruleid}."),
+    )
+    .await
+}
+
+#[command("remove")]
+#[aliases("delete", "-")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Removes an automod rule by its number."]
+#[example = "3"]
+async fn automod_remove(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let ruleid = match args.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No rule provided!",
+                "Please give me the number of the rule to remove, as shown by `automod list`.",
+            )
+            .await;
+        }
+    };
+    let guild = msg.guild_id.unwrap();
+    let removed = {
+        let conn = &match db_conn(ctx, msg, "remove an automod rule").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::remove_automod_rule(conn, guild.0, ruleid).await?
+    };
+    invalidate(ctx, guild).await;
+    match removed {
+        0 => {
+            send_err(
+                ctx,
+                msg,
+                format!("There's no automod rule #{ruleid} in this server."),
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Automod rule removed",
+                format!("I'll stop enforcing rule #{ruleid}."),
+            )
+            .await
+        }
+    }
+}
+
+#[command("list")]
+#[required_permissions("MANAGE_MESSAGES")]
+#[max_args(0)]
+#[description = "Shows all automod rules and the roles automod leaves alone."]
+async fn automod_list(ctx: &Context, msg: &Message) -> CommandResult {
+    let conf = match rules_for(ctx, msg.guild_id.unwrap()).await {
+        Some(conf) => conf,
+        None => return send_err(ctx, msg, "I couldn't load the automod rules.").await,
+    };
+    if conf.rules.is_empty() {
+        return send_ok(
+            ctx,
+            msg,
+            "Automod rules",
+            "There are no automod rules in this server.",
+        )
+        .await;
+    }
+    let mut lines: Vec<String> = conf
+        .rules
+        .iter()
+        .map(|r| format!("`#{}` {}", r.id, r.describe()))
+        .collect();
+    if !conf.exempt.is_empty() {
+        let roles: Vec<String> = conf.exempt.iter().map(|r| format!("<@&{}>", r.0)).collect();
+        lines.push(format!(
+            "\nMembers with these roles are left alone: {}",
+            roles.join(", ")
+        ));
+    }
+    send_ok(ctx, msg, "Automod rules", fit_embed(&lines)).await
+}
+
+#[command("test")]
+#[aliases("check")]
+#[required_permissions("MANAGE_MESSAGES")]
+#[min_args(1)]
+#[description = "Shows which automod rules a message would break, without acting on it."]
+#[example = "Join my server at discord.gg/example"]
+async fn automod_test(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let text = args.rest();
+    let conf = match rules_for(ctx, msg.guild_id.unwrap()).await {
+        Some(conf) => conf,
+        None => return send_err(ctx, msg, "I couldn't load the automod rules.").await,
+    };
+    let broken: Vec<String> = conf
+        .rules
+        .iter()
+        .filter(|r| r.matches(text))
+        .map(|r| format!("`#{}` {}", r.id, r.describe()))
+        .collect();
+    match broken.len() {
+        0 => {
+            send_ok(
+                ctx,
+                msg,
+                "Automod test",
+                "That message doesn't break any rules.",
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Automod test",
+                format!(
+                    "That message breaks these rules, I'd act on the first one:\n{}",
+                    fit_embed(&broken)
+                ),
+            )
+            .await
+        }
+    }
+}
+
+#[command("exempt")]
+#[aliases("allow")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Makes automod leave members with this role alone. Moderators are always left alone."]
+#[example = "@Trusted"]
+async fn automod_exempt(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    set_exempt(ctx, msg, &mut args, true).await
+}
+
+#[command("unexempt")]
+#[aliases("disallow")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Makes automod check members with this role again."]
+#[example = "@Trusted"]
+async fn automod_unexempt(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    set_exempt(ctx, msg, &mut args, false).await
+}
+
+/// # set_exempt
+/// Shared body of `exempt` and `unexempt`.
+async fn set_exempt(ctx: &Context, msg: &Message, args: &mut Args, exempt: bool) -> CommandResult {
+    args.trimmed();
+    let role = match args.parse::<RoleId>() {
+        Ok(role) => role,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No role provided!",
+                "Please give me a role mention or an ID.",
+            )
+            .await;
+        }
+    };
+    let guild = msg.guild_id.unwrap();
+    let changed = {
+        let conn = &match db_conn(ctx, msg, "change automod exemptions").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        match exempt {
+            true => dbx::add_automod_exempt(conn, guild.0, role.0).await?,
+            false => dbx::remove_automod_exempt(conn, guild.0, role.0).await?,
+        }
+    };
+    invalidate(ctx, guild).await;
+    match (exempt, changed) {
+        (true, 0) => send_err(ctx, msg, format!("<@&{}> was already exempt.", role.0)).await,
+        (true, _) => {
+            send_ok(
+                ctx,
+                msg,
+                "Role exempted",
+                format!("Automod will leave members with <@&{}> alone.", role.0),
+            )
+            .await
+        }
+        (false, 0) => send_err(ctx, msg, format!("<@&{}> wasn't exempt.", role.0)).await,
+        (false, _) => {
+            send_ok(
+                ctx,
+                msg,
+                "Exemption removed",
+                format!("Automod will check members with <@&{}> again.", role.0),
+            )
+            .await
+        }
+    }
+}
+
+#[group("Automod")]
+#[commands(
+    automod_add,
+    automod_remove,
+    automod_list,
+    automod_test,
+    automod_exempt,
+    automod_unexempt
+)]
+#[summary = "Automatically act on messages with banned words, patterns, zalgo, shouting or invite links. Shows the current rules if no extra arguments are given."]
+#[prefixes("automod")]
+#[default_command(automod_list)]
+#[only_in("guilds")]
+struct Automod;
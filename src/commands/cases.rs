@@ -0,0 +1,254 @@
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use crate::{
+    db_conn, dbx, fit_embed, get_color, get_name,
+    modlog::{action_name, case_embed, refresh_case},
+    sanitize_txt, send_err_titled, send_ok, zwei_conf,
+};
+
+#[command]
+#[required_permissions("MODERATE_MEMBERS")]
+#[num_args(1)]
+#[description = "Shows a moderation case by its number."]
+#[example = "12"]
+async fn case(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let caseno = match args.parse::<i64>() {
+        Ok(n) => n,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No case provided!",
+                "Please give me the number of the case to show.",
+            )
+            .await;
+        }
+    };
+    let guild_id = msg.guild_id.unwrap().0;
+    let found = {
+        let conn = &match db_conn(ctx, msg, "show a case").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_case(conn, guild_id, caseno).await?
+    };
+    let case = match found {
+        Some(case) => case,
+        None => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "Case not found!",
+                format!("There's no case #{caseno} in this server."),
+            )
+            .await;
+        }
+    };
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    msg.channel_id
+        .send_message(ctx, |m| m.embed(|e| case_embed(e, &case, color)))
+        .await?;
+    Ok(())
+}
+
+#[command]
+#[required_permissions("MODERATE_MEMBERS")]
+#[min_args(2)]
+#[aliases("amend")]
+#[description = "Changes the reason for a moderation case after the fact."]
+#[example = "12 Posting scam links"]
+async fn reason(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let caseno = match args.single::<i64>() {
+        Ok(n) => n,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No case provided!",
+                "Please give me the number of the case to amend, followed by the new reason.",
+            )
+            .await;
+        }
+    };
+    let reason = args.rest();
+    let guild = msg.guild_id.unwrap();
+    let amended = {
+        let conn = &match db_conn(ctx, msg, "amend a case").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        match dbx::set_case_reason(conn, guild.0, caseno, reason).await? {
+            0 => None,
+            _ => dbx::get_case(conn, guild.0, caseno).await?,
+        }
+    };
+    match amended {
+        Some(case) => {
+            refresh_case(ctx, guild, &case).await;
+            send_ok(
+                ctx,
+                msg,
+                "Case amended",
+                format!("Case #{caseno} now reads: {}", sanitize_txt(reason)),
+            )
+            .await
+        }
+        None => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Case not found!",
+                format!("There's no case #{caseno} in this server."),
+            )
+            .await
+        }
+    }
+}
+
+#[command]
+#[required_permissions("MODERATE_MEMBERS")]
+#[num_args(1)]
+#[aliases("cases", "modhistory")]
+#[description = "Lists all moderation cases for a member."]
+#[example = "@user"]
+async fn history(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+    if mem_id.0 == 0 {
+        return send_err_titled(
+            ctx,
+            msg,
+            "No target provided!",
+            "Please give me a user mention or an ID to look up.",
+        )
+        .await;
+    }
+    let guild_id = msg.guild_id.unwrap().0;
+    let cases = {
+        let conn = &match db_conn(ctx, msg, "list cases").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_user_cases(conn, guild_id, mem_id.0).await?
+    };
+    let fullname = get_name(msg, ctx, mem_id)
+        .await
+        .unwrap_or_else(|_| format!("<@{}>", mem_id.0));
+    if cases.is_empty() {
+        return send_ok(
+            ctx,
+            msg,
+            "No cases",
+            format!("{fullname} has never been in trouble here."),
+        )
+        .await;
+    }
+    let lines: Vec<String> = cases
+        .iter()
+        .map(|c| {
+            format!(
+                "`#{}` <t:{}:d> {} by <@{}>: {}",
+                c.caseno,
+                c.created,
+                action_name(&c.action),
+                c.moderator,
+                sanitize_txt(&c.reason)
+            )
+        })
+        .collect();
+    send_ok(ctx, msg, format!("Cases for {fullname}"), fit_embed(&lines)).await
+}
+
+#[command]
+#[required_permissions("MANAGE_GUILD")]
+#[max_args(1)]
+#[aliases("logchannel")]
+#[description = "Sets the channel I post moderation cases to. Use `none` to stop posting them, or nothing to see the current one."]
+#[example = "#mod-log"]
+#[example = "none"]
+async fn modlog(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild_id = msg.guild_id.unwrap().0;
+    let channel = match args.current() {
+        None => None,
+        Some(c) if c.eq_ignore_ascii_case("none") => Some(0),
+        Some(_) => match args.parse::<ChannelId>() {
+            Ok(c) => Some(c.0),
+            Err(_) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Invalid channel!",
+                    "Please give me a channel mention or an ID.",
+                )
+                .await;
+            }
+        },
+    };
+    let current = {
+        let conn = &match db_conn(ctx, msg, "set the mod-log").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        match channel {
+            Some(c) => {
+                dbx::set_modlog(conn, guild_id, c).await?;
+                c
+            }
+            None => dbx::get_modlog(conn, guild_id).await?,
+        }
+    };
+    match (channel, current) {
+        (None, 0) => {
+            send_ok(
+                ctx,
+                msg,
+                "Mod-log",
+                "There's no mod-log channel set, I'm only keeping cases in my notes.",
+            )
+            .await
+        }
+        (None, c) => {
+            send_ok(
+                ctx,
+                msg,
+                "Mod-log",
+                format!("I'm posting moderation cases to <#{c}>."),
+            )
+            .await
+        }
+        (Some(_), 0) => {
+            send_ok(
+                ctx,
+                msg,
+                "Mod-log unset",
+                "I'll stop posting moderation cases.",
+            )
+            .await
+        }
+        (Some(_), c) => {
+            send_ok(
+                ctx,
+                msg,
+                "Mod-log set",
+                format!("From now on I'll post moderation cases to <#{c}>."),
+            )
+            .await
+        }
+    }
+}
+
+#[group("Cases")]
+#[commands(case, reason, history, modlog)]
+#[summary = "Look up and amend the numbered cases I open for every moderation action."]
+#[only_in("guilds")]
+struct Cases;
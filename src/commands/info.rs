@@ -0,0 +1,424 @@
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use std::collections::BTreeMap;
+
+use crate::{
+    commands::notes::note_lines,
+    dbx::{self, ZweiDbConn},
+    fit_embed, fmt_duration, get_color,
+    modlog::action_name,
+    sanitize_txt, send_err, zwei_conf,
+};
+
+/// # fit_field
+/// Joins items into an embed field value. Field values cap out at 1024
+/// characters, so this keeps the first items that fit and mentions how many
+/// were left out.
+fn fit_field(items: &[String], sep: &str) -> String {
+    if items.is_empty() {
+        return "_None_".to_owned();
+    }
+    let mut charcount: usize = 0;
+    let kept = items
+        .iter()
+        .take_while(|item| {
+            charcount += item.chars().count() + sep.len();
+            charcount <= 1000
+        })
+        .count();
+    let shown = items[..kept].join(sep);
+    match items.len() - kept {
+        0 => shown,
+        hidden => format!("{shown}{sep}_...and {hidden} more_"),
+    }
+}
+
+/// # permission_list
+/// Renders permissions as a readable list, like `Kick Members, Ban Members`.
+fn permission_list(perms: Permissions) -> String {
+    match perms.is_empty() {
+        true => "_None_".to_owned(),
+        false => fit_field(
+            &perms
+                .get_permission_names()
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            ", ",
+        ),
+    }
+}
+
+/// # is_moderator
+/// Whether the invoker may see moderation records like warnings and notes.
+fn is_moderator(ctx: &Context, msg: &Message) -> bool {
+    msg.guild_id
+        .and_then(|g| g.to_guild_cached(ctx))
+        .and_then(|g| g.members.get(&msg.author.id).cloned())
+        .and_then(|m| m.permissions(ctx).ok())
+        .is_some_and(|perms| perms.moderate_members())
+}
+
+#[command]
+#[aliases("whois", "user", "ui")]
+#[max_args(1)]
+#[description = "Shows information about a member, or about you if nobody is given. Moderators also get to see their warnings, cases and notes."]
+#[example = "@user"]
+#[example = "123456789012345678"]
+async fn userinfo(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let target = match args.current() {
+        Some(_) => args.parse::<UserId>().unwrap_or_default(),
+        None => msg.author.id,
+    };
+    let guild = msg.guild_id.unwrap();
+    // Resolve like `get_name`: the member first, any user with that ID after.
+    let (user, member) = match guild.member(ctx, target).await {
+        Ok(member) => (member.user.clone(), Some(member)),
+        Err(_) => match target.to_user(ctx).await {
+            Ok(user) => (user, None),
+            Err(_) => {
+                return send_err(ctx, msg, "I couldn't find anyone by that ID.").await;
+            }
+        },
+    };
+
+    let created = user.id.created_at().unix_timestamp();
+    let mut fields = vec![
+        (
+            "User".to_owned(),
+            format!("<@{0}> (`{0}`)", user.id.0),
+            true,
+        ),
+        (
+            "Account created".to_owned(),
+            format!("<t:{created}:D> (<t:{created}:R>)"),
+            true,
+        ),
+    ];
+    match &member {
+        Some(member) => {
+            if let Some(joined) = member.joined_at {
+                let joined = joined.unix_timestamp();
+                fields.push((
+                    "Joined".to_owned(),
+                    format!("<t:{joined}:D> (<t:{joined}:R>)"),
+                    true,
+                ));
+            }
+            fields.push((
+                "Nickname".to_owned(),
+                match &member.nick {
+                    Some(nick) => sanitize_txt(nick),
+                    None => "_None_".to_owned(),
+                },
+                true,
+            ));
+            fields.push((
+                "Highest role".to_owned(),
+                match member.highest_role_info(ctx) {
+                    Some((role, _)) => format!("<@&{}>", role.0),
+                    None => "_None_".to_owned(),
+                },
+                true,
+            ));
+            let mut roles: Vec<(i64, RoleId)> = match guild.to_guild_cached(ctx) {
+                Some(g) => member
+                    .roles
+                    .iter()
+                    .map(|r| (g.roles.get(r).map(|r| r.position).unwrap_or(0), *r))
+                    .collect(),
+                None => member.roles.iter().map(|r| (0, *r)).collect(),
+            };
+            roles.sort_unstable_by(|a, b| b.cmp(a));
+            let roles: Vec<String> = roles.iter().map(|(_, r)| format!("<@&{}>", r.0)).collect();
+            fields.push((
+                format!("Roles ({})", roles.len()),
+                fit_field(&roles, " "),
+                false,
+            ));
+        }
+        None => fields.push(("Member".to_owned(), "Not in this server".to_owned(), true)),
+    }
+
+    let mut notes = String::new();
+    if is_moderator(ctx, msg) {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => {
+                let warns = dbx::get_warnings(conn, guild.0, user.id.0).await?;
+                let cutoff = dbx::warn_cutoff(conn, guild.0).await?;
+                let active = warns.iter().filter(|w| w.issued >= cutoff).count();
+                let cases = dbx::get_user_cases(conn, guild.0, user.id.0).await?;
+                let user_notes = dbx::get_notes(conn, guild.0, user.id.0).await?;
+                fields.push((
+                    "Warnings".to_owned(),
+                    format!("{active} active, {} total", warns.len()),
+                    true,
+                ));
+                fields.push(("Cases".to_owned(), cases.len().to_string(), true));
+                if !user_notes.is_empty() {
+                    notes = format!("**Notes**\n{}", fit_embed(&note_lines(&user_notes)));
+                }
+            }
+            None => log::error!(
+                "Failed to acquire database connection object to show moderation records!"
+            ),
+        }
+    }
+
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    let avatar = user.face();
+    let title = match &member {
+        Some(member) => member.display_name().into_owned(),
+        None => user.tag(),
+    };
+    msg.channel_id
+        .send_message(ctx, |mes| {
+            mes.embed(|e| {
+                e.color(color).title(title).thumbnail(avatar).fields(fields);
+                if !notes.is_empty() {
+                    e.description(notes);
+                }
+                e
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+#[command]
+#[aliases("guildinfo", "server", "si")]
+#[max_args(0)]
+#[description = "Shows information about this server."]
+async fn serverinfo(ctx: &Context, msg: &Message) -> CommandResult {
+    let g = match msg.guild(ctx) {
+        Some(g) => g,
+        None => return send_err(ctx, msg, "I can't see this server right now.").await,
+    };
+    let created = g.id.created_at().unix_timestamp();
+    let mut kinds: BTreeMap<String, usize> = BTreeMap::new();
+    for channel in g.channels.values() {
+        let kind = match channel {
+            Channel::Guild(c) => c.kind,
+            Channel::Category(c) => c.kind,
+            _ => continue,
+        };
+        *kinds.entry(kind.name().to_owned()).or_default() += 1;
+    }
+    let channels: Vec<String> = kinds
+        .iter()
+        .map(|(kind, n)| format!("{n} {kind}"))
+        .collect();
+    let features: Vec<String> = g
+        .features
+        .iter()
+        .map(|f| action_name(&f.to_lowercase().replace('_', " ")))
+        .collect();
+    let tier = match g.premium_tier {
+        PremiumTier::Tier1 => "Level 1",
+        PremiumTier::Tier2 => "Level 2",
+        PremiumTier::Tier3 => "Level 3",
+        _ => "No level",
+    };
+
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    msg.channel_id
+        .send_message(ctx, |mes| {
+            mes.embed(|e| {
+                e.color(color)
+                    .title(&g.name)
+                    .field("Server", format!("`{}`", g.id.0), true)
+                    .field("Owner", format!("<@{}>", g.owner_id.0), true)
+                    .field(
+                        "Created",
+                        format!("<t:{created}:D> (<t:{created}:R>)"),
+                        true,
+                    )
+                    .field("Members", g.member_count.to_string(), true)
+                    .field("Roles", g.roles.len().to_string(), true)
+                    .field("Emojis", g.emojis.len().to_string(), true)
+                    .field(
+                        "Boosts",
+                        format!("{tier}, {} boosts", g.premium_subscription_count),
+                        true,
+                    )
+                    .field("Verification", format!("{:?}", g.verification_level), true)
+                    .field(
+                        format!("Channels ({})", g.channels.len()),
+                        fit_field(&channels, ", "),
+                        false,
+                    )
+                    .field("Features", fit_field(&features, ", "), false);
+                if let Some(icon) = g.icon_url() {
+                    e.thumbnail(icon);
+                }
+                e
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+#[command]
+#[aliases("role", "ri")]
+#[min_args(1)]
+#[description = "Shows information about a role, by mention, ID or name."]
+#[example = "@Moderators"]
+#[example = "Moderators"]
+async fn roleinfo(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let g = match msg.guild(ctx) {
+        Some(g) => g,
+        None => return send_err(ctx, msg, "I can't see this server right now.").await,
+    };
+    let wanted = args.rest().trim();
+    let role = match wanted.parse::<RoleId>() {
+        Ok(id) => g.roles.get(&id),
+        Err(_) => g
+            .roles
+            .values()
+            .find(|r| r.name.eq_ignore_ascii_case(wanted)),
+    };
+    let role = match role {
+        Some(role) => role,
+        None => return send_err(ctx, msg, "I couldn't find that role in this server.").await,
+    };
+    let created = role.id.created_at().unix_timestamp();
+    let members = match role.id.0 == g.id.0 {
+        true => g.member_count as usize,
+        false => g
+            .members
+            .values()
+            .filter(|m| m.roles.contains(&role.id))
+            .count(),
+    };
+    let yesno = |b: bool| if b { "Yes" } else { "No" };
+    let colour = match role.colour.0 {
+        0 => "Default".to_owned(),
+        _ => format!("#{}", role.colour.hex()),
+    };
+
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    msg.channel_id
+        .send_message(ctx, |mes| {
+            mes.embed(|e| {
+                e.color(color)
+                    .title(&role.name)
+                    .field("Role", format!("<@&{0}> (`{0}`)", role.id.0), true)
+                    .field(
+                        "Created",
+                        format!("<t:{created}:D> (<t:{created}:R>)"),
+                        true,
+                    )
+                    .field("Members", members.to_string(), true)
+                    .field("Colour", colour, true)
+                    .field("Position", role.position.to_string(), true)
+                    .field("Shown separately", yesno(role.hoist), true)
+                    .field("Mentionable", yesno(role.mentionable), true)
+                    .field("Managed by an integration", yesno(role.managed), true)
+                    .field("Permissions", permission_list(role.permissions), false)
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+#[command]
+#[aliases("channel", "ci")]
+#[max_args(1)]
+#[description = "Shows information about a channel, or this one if none is given. Includes the permission overwrites set on it."]
+#[example = "#general"]
+async fn channelinfo(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let channel_id = match args.current() {
+        Some(_) => match args.parse::<ChannelId>() {
+            Ok(id) => id,
+            Err(_) => {
+                return send_err(ctx, msg, "Please give me a channel mention or an ID.").await
+            }
+        },
+        None => msg.channel_id,
+    };
+    let channel = match channel_id.to_channel(ctx).await {
+        Ok(Channel::Guild(c)) if Some(c.guild_id) == msg.guild_id => c,
+        _ => return send_err(ctx, msg, "I couldn't find that channel in this server.").await,
+    };
+    let created = channel.id.created_at().unix_timestamp();
+    let overwrites: Vec<String> = channel
+        .permission_overwrites
+        .iter()
+        .map(|o| {
+            let who = match o.kind {
+                PermissionOverwriteType::Role(r) if r.0 == channel.guild_id.0 => {
+                    "@everyone".to_owned()
+                }
+                PermissionOverwriteType::Role(r) => format!("<@&{}>", r.0),
+                PermissionOverwriteType::Member(u) => format!("<@{}>", u.0),
+                _ => "Unknown".to_owned(),
+            };
+            let mut line = format!("**{who}**");
+            if !o.allow.is_empty() {
+                line.push_str(&format!(
+                    "\n+ {}",
+                    o.allow.get_permission_names().join(", ")
+                ));
+            }
+            if !o.deny.is_empty() {
+                line.push_str(&format!("\n- {}", o.deny.get_permission_names().join(", ")));
+            }
+            line
+        })
+        .collect();
+
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    msg.channel_id
+        .send_message(ctx, |mes| {
+            mes.embed(|e| {
+                e.color(color)
+                    .title(&channel.name)
+                    .field("Channel", format!("<#{0}> (`{0}`)", channel.id.0), true)
+                    .field("Type", action_name(channel.kind.name()), true)
+                    .field(
+                        "Created",
+                        format!("<t:{created}:D> (<t:{created}:R>)"),
+                        true,
+                    )
+                    .field(
+                        "Category",
+                        match channel.parent_id {
+                            Some(p) => format!("<#{}>", p.0),
+                            None => "_None_".to_owned(),
+                        },
+                        true,
+                    )
+                    .field("Position", channel.position.to_string(), true)
+                    .field("NSFW", if channel.nsfw { "Yes" } else { "No" }, true);
+                if let Some(secs) = channel.rate_limit_per_user.filter(|s| *s > 0) {
+                    e.field("Slowmode", fmt_duration(secs), true);
+                }
+                if let Some(topic) = channel.topic.as_ref().filter(|t| !t.is_empty()) {
+                    e.description(topic);
+                }
+                e.field(
+                    format!("Permission overwrites ({})", overwrites.len()),
+                    fit_field(&overwrites, "\n"),
+                    false,
+                )
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+#[group("Info")]
+#[commands(userinfo, serverinfo, roleinfo, channelinfo)]
+#[summary = "Look up information about members and the server."]
+#[only_in("guilds")]
+struct Info;
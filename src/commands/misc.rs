@@ -12,21 +12,25 @@ use serenity::{
 use tokio::time::{sleep, Duration};
 
 use crate::{
-    dbx, get_name, get_prefix, send_err, send_err_titled, send_ok, ShardManagerContainer, ZweiData,
-    ZweiDbConn, ZweiOwners, ZweiPrefixes,
+    dbx, fmt_duration, get_name, get_prefix, parse_duration, send_err, send_err_titled, send_ok,
+    ShardManagerContainer, ZweiData, ZweiDbConn, ZweiOwners, ZweiPrefixes,
 };
 
 #[command]
 #[owners_only]
 #[max_args(1)]
 #[aliases("shutdown", "panic", "die", "sleep")]
-#[description = "Stops me in my tracks. Optionally takes a time in seconds to wait, defaults to 1 second."]
+#[description = "Stops me in my tracks. Optionally takes a time to wait, either in seconds or like `1m30s`. Defaults to 1 second."]
 #[example = "5"]
+#[example = "2m"]
 #[example = ""]
 #[help_available]
 async fn exit(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     args.trimmed();
-    let time: u64 = args.parse::<u64>().unwrap_or(1);
+    let time: u64 = args
+        .current()
+        .and_then(|t| t.parse::<u64>().ok().or_else(|| parse_duration(t)))
+        .unwrap_or(1);
     log::info!(
         "Received a shutdown request at UTC {} with {time}s timeout",
         Utc::now()
@@ -37,20 +41,7 @@ async fn exit(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
             ctx,
             msg,
             "Shutting down",
-            format!(
-                "I'm going down in {}.",
-                if time >= 60 {
-                    let secs = time % 60;
-                    let mins = (time - secs) / 60;
-                    format!(
-                        "{mins} minute{} and {secs} second{}",
-                        if mins != 1 { "s" } else { "" },
-                        if secs != 1 { "s" } else { "" }
-                    )
-                } else {
-                    format!("{time} second{}", if time != 1 { "s" } else { "" })
-                }
-            ),
+            format!("I'm going down in {}.", fmt_duration(time)),
         )
         .await?;
         sleep(Duration::from_secs(time)).await;
@@ -246,7 +237,6 @@ async fn clear(ctx: &Context, msg: &Message) -> CommandResult {
                     log::warn!("Removed {n} prefixes for guild {guild}...");
                     let etxt = "Prefix change affected multiple rows...";
                     send_err_titled(ctx, msg, "Clear prefix", etxt).await?;
-                    ()
                 }
             };
         } else {
@@ -1,3 +1,11 @@
+pub mod antiraid;
+pub mod antispam;
+pub mod appeals;
+pub mod automod;
+pub mod cases;
+pub mod info;
 pub mod misc;
 pub mod modtools;
+pub mod names;
+pub mod notes;
 pub mod subs;
@@ -1,95 +1,2291 @@
+use chrono::Utc;
 use log;
+use regex::{Regex, RegexBuilder};
 use serenity::{
     framework::standard::{
         macros::{command, group},
         Args, CommandResult,
     },
+    http::{Http, StatusCode},
+    json::{JsonMap, Value},
     model::{id::UserId, prelude::*},
     prelude::*,
-    Error,
+    Error, Result as SerenityResult,
 };
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    appeals, confirm, db_conn,
+    dbx::{self, Pool, ZweiDbConn},
+    fit_embed, fmt_duration, get_color, get_name,
+    guard::{check, guard, ModerationDenied},
+    modlog::log_case,
+    names, parse_duration, sanitize_txt,
+    scheduler::{schedule, MAX_DELAY},
+    send_err, send_err_titled, send_ok, try_dm, zwei_conf,
+};
+
+/// # failure_reason
+/// Turns the error Discord gave us for a moderation action into something a
+/// moderator can act on.
+fn failure_reason(e: &Error) -> String {
+    match e {
+        Error::Model(ModelError::InvalidPermissions(missing_perms)) => {
+            format!("please provide me with the `{missing_perms}` permission")
+        }
+        Error::Other(why) => why.to_string(),
+        _ => "the provided reason was too long".to_owned(),
+    }
+}
+
+/// # notify_member
+/// Tries to let a member know what's being done to them and why. Used by all
+/// moderation actions, `what` completes the sentence "You were ..." up to the
+/// name of the server, e.g. "kicked from".
+async fn notify_member(ctx: &Context, guild: GuildId, target: UserId, what: &str, reason: &str) {
+    let guildname = guild
+        .name(ctx)
+        .unwrap_or_else(|| String::from("Unknown Server"));
+    let _ = try_dm(
+        ctx,
+        target,
+        "<:ZweiShy:844167336336031745> Sorry!",
+        format!("You were {what} {guildname}.\nReason: {reason}"),
+    )
+    .await;
+}
+
+/// # kick_member
+/// Lets a member know they're being kicked and why, then kicks them with the
+/// reason in the audit log. Shared by `kick` and warning escalations.
+pub(crate) async fn kick_member(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    reason: &str,
+) -> SerenityResult<()> {
+    notify_member(ctx, guild, target, "kicked from", reason).await;
+    guild.kick_with_reason(ctx, target, reason).await
+}
+
+/// # ban_member
+/// Lets a member know they're being banned and why, then bans them with the
+/// reason in the audit log. `days` is the amount of days worth of messages to
+/// delete and must not exceed 7. If `secs` is set, the ban is lifted again by
+/// the scheduler after that many seconds. Any previously scheduled unban for
+/// this member is cancelled, so a new permanent ban stays permanent.
+/// If the guild takes appeals, the notice includes an appeal ID.
+/// Shared by `ban` and warning escalations.
+pub(crate) async fn ban_member(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    days: u8,
+    secs: Option<u64>,
+    reason: &str,
+) -> SerenityResult<()> {
+    let what = match secs {
+        Some(secs) => format!("banned for {} from", fmt_duration(secs)),
+        None => "banned from".to_owned(),
+    };
+    let appeal = appeals::open_appeal(ctx, guild, target, reason).await;
+    match appeal {
+        Some(appealid) => {
+            let notice = format!(
+                "{reason}\n\nIf you think this was a mistake, you can appeal by sending me `;appeal {appealid} <your message>`."
+            );
+            notify_member(ctx, guild, target, &what, &notice).await;
+        }
+        None => notify_member(ctx, guild, target, &what, reason).await,
+    }
+    if let Err(e) = guild.ban_with_reason(ctx, target, days, reason).await {
+        if let Some(appealid) = appeal {
+            appeals::drop_appeal(ctx, appealid).await;
+        }
+        return Err(e);
+    }
+
+    let cancelled = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => dbx::cancel_scheduled(conn, guild.0, target.0, "unban").await,
+            None => Err(dbx::SQLerr::PoolClosed),
+        }
+    };
+    let scheduled = match (cancelled, secs) {
+        (Err(e), _) => Err(e),
+        (Ok(_), Some(secs)) => schedule(ctx, guild.0, target.0, "unban", secs, 0)
+            .await
+            .map(|_| ()),
+        (Ok(_), None) => Ok(()),
+    };
+    scheduled.map_err(|e| {
+        log::error!(
+            "Couldn't schedule the unban for {} in {}\n\t{e}",
+            target.0,
+            guild.0
+        );
+        Error::Other("the ban went through, but I couldn't schedule lifting it")
+    })
+}
+
+/// # unban_member
+/// Lifts a ban with the reason in the audit log, cancelling any unban that was
+/// still scheduled for it. Also tries to let the user know, though that only
+/// works if they still share a server with me.
+pub(crate) async fn unban_member(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    reason: &str,
+) -> SerenityResult<()> {
+    ctx.http.remove_ban(guild.0, target.0, Some(reason)).await?;
+    {
+        let botdata = ctx.data.read().await;
+        if let Some(conn) = botdata.get::<ZweiDbConn>() {
+            // Failing to cancel isn't fatal, the scheduler will just find the
+            // user already unbanned.
+            let _ = dbx::cancel_scheduled(conn, guild.0, target.0, "unban").await;
+        }
+    }
+    notify_member(ctx, guild, target, "unbanned from", reason).await;
+    Ok(())
+}
+
+/// # MAX_TIMEOUT
+/// The longest Discord lets anyone be timed out for, 28 days in seconds.
+pub(crate) const MAX_TIMEOUT: u64 = 28 * 86400;
+
+/// # timeout_member
+/// Times a member out for the given amount of seconds, which must not exceed
+/// [`MAX_TIMEOUT`], and lets them know why.
+pub(crate) async fn timeout_member(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    secs: u64,
+    reason: &str,
+) -> SerenityResult<()> {
+    let until = Utc::now() + chrono::Duration::seconds(secs as i64);
+    let mut map = JsonMap::new();
+    map.insert(
+        "communication_disabled_until".to_owned(),
+        Value::from(until.to_rfc3339()),
+    );
+    ctx.http
+        .edit_member(guild.0, target.0, &map, Some(reason))
+        .await?;
+    let what = format!("timed out for {} in", fmt_duration(secs));
+    notify_member(ctx, guild, target, &what, reason).await;
+    Ok(())
+}
+
+/// # mute_with_role
+/// Mutes a member by giving them the guild's muted role and lets them know why.
+/// The mute is stored so it can be reapplied if they leave and rejoin, and is
+/// lifted again by the scheduler if `secs` is set.
+pub(crate) async fn mute_with_role(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    role: RoleId,
+    secs: Option<u64>,
+    reason: &str,
+) -> SerenityResult<()> {
+    ctx.http
+        .add_member_role(guild.0, target.0, role.0, Some(reason))
+        .await?;
+    let stored = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => {
+                let _ = dbx::cancel_scheduled(conn, guild.0, target.0, "unmute").await;
+                dbx::add_mute(conn, guild.0, target.0).await.map(|_| ())
+            }
+            None => Err(dbx::SQLerr::PoolClosed),
+        }
+    };
+    let scheduled = match (stored, secs) {
+        (Err(e), _) => Err(e),
+        (Ok(_), Some(secs)) => schedule(ctx, guild.0, target.0, "unmute", secs, 0)
+            .await
+            .map(|_| ()),
+        (Ok(_), None) => Ok(()),
+    };
+    if let Err(e) = scheduled {
+        log::error!(
+            "Couldn't store the mute for {} in {}\n\t{e}",
+            target.0,
+            guild.0
+        );
+        return Err(Error::Other(
+            "they have the muted role, but I couldn't store the mute",
+        ));
+    }
+    let what = match secs {
+        Some(secs) => format!("muted for {} in", fmt_duration(secs)),
+        None => "muted in".to_owned(),
+    };
+    notify_member(ctx, guild, target, &what, reason).await;
+    Ok(())
+}
+
+/// # unmute_member
+/// Lifts both a timeout and a role-based mute, whichever apply, and lets the
+/// member know. Members who left only have their stored mute forgotten, so it
+/// isn't put back when they rejoin. Returns `false` if they weren't muted in
+/// the first place.
+pub(crate) async fn unmute_member(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    reason: &str,
+) -> SerenityResult<bool> {
+    let member = match guild.member(ctx, target).await {
+        Ok(member) => member,
+        Err(Error::Http(e)) if e.status_code() == Some(StatusCode::NOT_FOUND) => {
+            let removed = {
+                let botdata = ctx.data.read().await;
+                match botdata.get::<ZweiDbConn>() {
+                    Some(conn) => {
+                        let _ = dbx::cancel_scheduled(conn, guild.0, target.0, "unmute").await;
+                        dbx::remove_mute(conn, guild.0, target.0).await
+                    }
+                    None => Err(dbx::SQLerr::PoolClosed),
+                }
+            };
+            return removed.map(|n| n > 0).map_err(|e| {
+                log::error!(
+                    "Couldn't forget the mute of {} in {}\n\t{e}",
+                    target.0,
+                    guild.0
+                );
+                Error::Other("they left, and I couldn't forget about their mute")
+            });
+        }
+        Err(e) => return Err(e),
+    };
+    let mut unmuted = false;
+    if member
+        .communication_disabled_until
+        .is_some_and(|until| until.unix_timestamp() > Utc::now().timestamp())
+    {
+        let mut map = JsonMap::new();
+        map.insert("communication_disabled_until".to_owned(), Value::Null);
+        ctx.http
+            .edit_member(guild.0, target.0, &map, Some(reason))
+            .await?;
+        unmuted = true;
+    }
+    let role = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => {
+                let _ = dbx::cancel_scheduled(conn, guild.0, target.0, "unmute").await;
+                let _ = dbx::remove_mute(conn, guild.0, target.0).await;
+                dbx::get_mute_role(conn, guild.0).await.unwrap_or(0)
+            }
+            None => 0,
+        }
+    };
+    if role != 0 && member.roles.contains(&RoleId(role)) {
+        ctx.http
+            .remove_member_role(guild.0, target.0, role, Some(reason))
+            .await?;
+        unmuted = true;
+    }
+    if unmuted {
+        notify_member(ctx, guild, target, "unmuted in", reason).await;
+    }
+    Ok(unmuted)
+}
+
+/// # reapply_mute
+/// Gives the muted role back to members that left while muted with it, so
+/// leaving and rejoining doesn't get anyone out of a mute. Called from the
+/// `guild_member_addition` event.
+pub(crate) async fn reapply_mute(ctx: &Context, member: &Member) {
+    let guild = member.guild_id.0;
+    let user = member.user.id.0;
+    let role = {
+        let botdata = ctx.data.read().await;
+        let conn = match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn,
+            None => return,
+        };
+        match dbx::is_muted(conn, guild, user).await {
+            Ok(true) => dbx::get_mute_role(conn, guild).await.unwrap_or(0),
+            _ => 0,
+        }
+    };
+    if role == 0 {
+        return;
+    }
+    if let Err(e) = ctx
+        .http
+        .add_member_role(guild, user, role, Some("Reapplying mute after rejoining"))
+        .await
+    {
+        log::error!("Couldn't reapply the mute for {user} in {guild}!\n\t{e}");
+    }
+}
+
+/// # MAX_NICK
+/// The longest nickname Discord allows.
+pub(crate) const MAX_NICK: usize = 32;
+
+/// # rename_member
+/// Changes a member's nickname with the reason in the audit log. An empty
+/// nickname resets it to their username. Shared by `nick`, `dehoist` and the
+/// name rules.
+pub(crate) async fn rename_member(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    nick: &str,
+    reason: &str,
+) -> SerenityResult<()> {
+    let mut map = JsonMap::new();
+    map.insert("nick".to_owned(), Value::from(nick));
+    ctx.http
+        .edit_member(guild.0, target.0, &map, Some(reason))
+        .await
+        .map(|_| ())
+}
+
+/// # dehoisted
+/// Strips the leading symbols people use to get to the top of the member
+/// list. Returns `None` if the name wasn't hoisted, and an empty string if
+/// nothing is left of it.
+pub(crate) fn dehoisted(name: &str) -> Option<String> {
+    let stripped = name.trim_start_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace());
+    match stripped.len() == name.len() {
+        true => None,
+        false => Some(stripped.to_owned()),
+    }
+}
+
+/// # guild_channel
+/// Fetches a channel, as long as it's in this guild. Keeps moderation commands
+/// from being pointed at channels of other servers.
+async fn guild_channel(ctx: &Context, guild: GuildId, channel: ChannelId) -> Option<GuildChannel> {
+    match channel.to_channel(ctx).await {
+        Ok(Channel::Guild(c)) if c.guild_id == guild => Some(c),
+        _ => None,
+    }
+}
+
+/// # lock_channel
+/// Denies `@everyone` from sending messages in a channel, after storing the
+/// overwrite it had so [`unlock_channel`] can put it back exactly. Returns
+/// `false` if the channel was already locked.
+pub(crate) async fn lock_channel(
+    ctx: &Context,
+    guild: GuildId,
+    channel: ChannelId,
+) -> CommandResult<bool> {
+    let conn = match ctx.data.read().await.get::<ZweiDbConn>() {
+        Some(conn) => conn.clone(),
+        None => return Err("Could not acquire the database connection object".into()),
+    };
+    if dbx::get_lock(&conn, guild.0, channel.0).await?.is_some() {
+        return Ok(false);
+    }
+    let everyone = PermissionOverwriteType::Role(RoleId(guild.0));
+    let current = channel.to_channel(ctx).await?.guild().and_then(|c| {
+        c.permission_overwrites
+            .into_iter()
+            .find(|o| o.kind == everyone)
+    });
+    let (allow, deny) = match &current {
+        Some(o) => (o.allow, o.deny),
+        None => (Permissions::empty(), Permissions::empty()),
+    };
+    dbx::add_lock(
+        &conn,
+        guild.0,
+        channel.0,
+        allow.bits(),
+        deny.bits(),
+        current.is_some(),
+    )
+    .await?;
+    let locked = PermissionOverwrite {
+        allow: allow - Permissions::SEND_MESSAGES,
+        deny: deny | Permissions::SEND_MESSAGES,
+        kind: everyone,
+    };
+    if let Err(e) = channel.create_permission(ctx, &locked).await {
+        let _ = dbx::remove_lock(&conn, guild.0, channel.0).await;
+        return Err(e.into());
+    }
+    Ok(true)
+}
+
+/// # unlock_channel
+/// Restores the `@everyone` overwrite a channel had before it was locked, or
+/// removes it if there wasn't one. Takes the HTTP client and database pool
+/// directly, so the scheduler can lift locks when they expire. Returns `false`
+/// if the channel wasn't locked in this guild.
+pub(crate) async fn unlock_channel(
+    http: &Http,
+    conn: &Pool,
+    guild: u64,
+    channel: u64,
+) -> CommandResult<bool> {
+    let lock = match dbx::get_lock(conn, guild, channel).await? {
+        Some(lock) => lock,
+        None => return Ok(false),
+    };
+    let everyone = PermissionOverwriteType::Role(RoleId(lock.serverid as u64));
+    if lock.existed {
+        let original = PermissionOverwrite {
+            allow: Permissions::from_bits_truncate(lock.allow_bits as u64),
+            deny: Permissions::from_bits_truncate(lock.deny_bits as u64),
+            kind: everyone,
+        };
+        ChannelId(channel)
+            .create_permission(http, &original)
+            .await?;
+    } else {
+        ChannelId(channel).delete_permission(http, everyone).await?;
+    }
+    dbx::remove_lock(conn, guild, channel).await?;
+    Ok(true)
+}
+
+/// # warn_member
+/// Stores a warning, opens a case for it and lets the member know. Shared by
+/// `warn` and automatic moderation. Returns the member's warning count and
+/// the escalation rule it triggers, if any, so the caller can apply it.
+pub(crate) async fn warn_member(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    moderator: UserId,
+    reason: &str,
+) -> CommandResult<(i32, Option<dbx::Escalation>)> {
+    let (count, escalation) = {
+        let botdata = ctx.data.read().await;
+        let conn = botdata
+            .get::<ZweiDbConn>()
+            .ok_or("Could not acquire the database connection object")?;
+        let count = dbx::add_warning(conn, guild.0, target.0, moderator.0, reason).await?;
+        let escalation = dbx::get_escalation(conn, guild.0, target.0, count).await?;
+        (count, escalation)
+    };
+    log_case(ctx, guild, moderator, target.0, "warn", reason).await;
+    let guildname = guild
+        .name(ctx)
+        .unwrap_or_else(|| String::from("Unknown Server"));
+    let _ = try_dm(
+        ctx,
+        target,
+        "<:ZweiShy:844167336336031745> Careful!",
+        format!("You were warned in {guildname}.\nReason: {reason}\nThis is warning #{count}."),
+    )
+    .await;
+    Ok((count, escalation))
+}
+
+/// # escalate
+/// Applies an escalation rule to a member that just reached its threshold.
+/// This goes through the same paths as `kick` and `ban`, so the member gets a
+/// DM and the audit log gets a reason. Members the moderation guard protects
+/// are left alone. The rule only counts as applied once the action went
+/// through, so a failed one fires again on the next warning. Returns what was
+/// done, for the reply.
+pub(crate) async fn escalate(
+    ctx: &Context,
+    guild: GuildId,
+    target: UserId,
+    count: i32,
+    rule: &dbx::Escalation,
+) -> SerenityResult<String> {
+    if let Err(why) = check(ctx, guild, None, target).await {
+        log::warn!(
+            "Not escalating to {} on {} in guild {}: {why:?}",
+            rule.action,
+            target.0,
+            guild.0
+        );
+        return Err(Error::Other(match why {
+            ModerationDenied::Owner => "they own the server",
+            ModerationDenied::AboveMe => "their roles are equal to or higher than mine",
+            ModerationDenied::Unverified => "I couldn't check whether I'm allowed to",
+            _ => "they're out of my reach",
+        }));
+    }
+    let reason = format!("Automatic escalation after {count} warnings");
+    let done = match rule.action.as_str() {
+        "timeout" => {
+            let secs = rule.duration as u64;
+            timeout_member(ctx, guild, target, secs, &reason).await?;
+            format!("timed them out for {}", fmt_duration(secs))
+        }
+        "kick" => {
+            kick_member(ctx, guild, target, &reason).await?;
+            "kicked them".to_owned()
+        }
+        "ban" => {
+            ban_member(ctx, guild, target, 0, None, &reason).await?;
+            "banned them".to_owned()
+        }
+        other => {
+            log::error!("Unknown escalation action {other} for guild {}", guild.0);
+            return Err(Error::Other("Unknown escalation action"));
+        }
+    };
+    if let Some(conn) = ctx.data.read().await.get::<ZweiDbConn>() {
+        if let Err(e) = dbx::set_escalated(conn, guild.0, target.0, rule.threshold).await {
+            log::error!(
+                "Couldn't store the escalation of {} in guild {}\n\t{e}",
+                target.0,
+                guild.0
+            );
+        }
+    }
+    let me = ctx.cache.current_user_id();
+    log_case(ctx, guild, me, target.0, &rule.action, &reason).await;
+    Ok(done)
+}
+
+/// # PurgeFilter
+/// A single filter `purge` can narrow its messages down with. Messages have to
+/// match every given filter to be deleted.
+enum PurgeFilter {
+    User(UserId),
+    Bots,
+    Attachments,
+    Links,
+    Contains(String),
+    Regex(Regex),
+}
+
+impl PurgeFilter {
+    fn matches(&self, msg: &Message) -> bool {
+        match self {
+            PurgeFilter::User(id) => msg.author.id == *id,
+            PurgeFilter::Bots => msg.author.bot,
+            PurgeFilter::Attachments => !msg.attachments.is_empty(),
+            PurgeFilter::Links => {
+                msg.content.contains("http://") || msg.content.contains("https://")
+            }
+            PurgeFilter::Contains(txt) => msg.content.to_lowercase().contains(txt),
+            PurgeFilter::Regex(re) => re.is_match(&msg.content),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            PurgeFilter::User(id) => format!("`--user` <@{}>", id.0),
+            PurgeFilter::Bots => "`--bots`".to_owned(),
+            PurgeFilter::Attachments => "`--has-attachments`".to_owned(),
+            PurgeFilter::Links => "`--has-links`".to_owned(),
+            PurgeFilter::Contains(txt) => format!("`--contains` \"{}\"", sanitize_txt(txt)),
+            PurgeFilter::Regex(re) => format!("`--regex` `{}`", re.as_str().replace('`', "'")),
+        }
+    }
+}
+
+/// # parse_purge_filters
+/// Reads the flags following the amount for `purge`. Text for `--contains` and
+/// `--regex` can be wrapped in quotes to include spaces. Returns a message for
+/// the invoker if a flag is unknown or its value is missing or invalid.
+fn parse_purge_filters(args: &mut Args) -> Result<Vec<PurgeFilter>, String> {
+    let mut filters = Vec::new();
+    while !args.is_empty() {
+        let flag = args.single::<String>().unwrap_or_default();
+        let filter = match flag.to_lowercase().as_str() {
+            "--user" => match args.single::<UserId>() {
+                Ok(id) => PurgeFilter::User(id),
+                Err(_) => return Err("`--user` needs a user mention or an ID.".to_owned()),
+            },
+            "--bots" => PurgeFilter::Bots,
+            "--has-attachments" | "--attachments" => PurgeFilter::Attachments,
+            "--has-links" | "--links" => PurgeFilter::Links,
+            "--contains" => match args.single_quoted::<String>() {
+                Ok(txt) if !txt.is_empty() => PurgeFilter::Contains(txt.to_lowercase()),
+                _ => return Err("`--contains` needs some text to look for.".to_owned()),
+            },
+            "--regex" => {
+                let pattern = match args.single_quoted::<String>() {
+                    Ok(p) if !p.is_empty() => p,
+                    _ => return Err("`--regex` needs a pattern to match.".to_owned()),
+                };
+                match RegexBuilder::new(&pattern).size_limit(1 << 16).build() {
+                    Ok(re) => PurgeFilter::Regex(re),
+                    Err(_) => return Err(format!("`{pattern}` isn't a valid pattern.")),
+                }
+            }
+            other => {
+                return Err(format!(
+                    "I don't know the filter `{}`. Try `--user`, `--bots`, `--has-attachments`, `--has-links`, `--contains` or `--regex`.",
+                    sanitize_txt(other)
+                ))
+            }
+        };
+        filters.push(filter);
+    }
+    Ok(filters)
+}
+
+/// # filter_matches
+/// Checks a message against every filter, counting a match for each filter it
+/// passes on its own. Returns whether the message passed all of them.
+fn filter_matches(filters: &[PurgeFilter], matched: &mut [usize], msg: &Message) -> bool {
+    let mut keep = true;
+    for (filter, count) in filters.iter().zip(matched.iter_mut()) {
+        if filter.matches(msg) {
+            *count += 1;
+        } else {
+            keep = false;
+        }
+    }
+    keep
+}
+
+/// The most messages a single `purge` will look through.
+const MAX_PURGE: u64 = 5000;
+/// Discord refuses to bulk delete messages older than two weeks. Leave a
+/// minute of slack so messages don't age out while a purge is running.
+const BULK_DELETE_AGE: i64 = 14 * 24 * 60 * 60 - 60;
+
+/// # ZweiPurges
+/// A `TypeMapKey` holding the purges currently running, keyed by channel.
+/// Setting the flag for a channel makes its purge stop after the current batch.
+pub struct ZweiPurges;
+impl TypeMapKey for ZweiPurges {
+    type Value = HashMap<u64, Arc<AtomicBool>>;
+}
+
+#[command]
+#[required_permissions("MANAGE_MESSAGES")]
+#[only_in("guilds")]
+#[min_args(1)]
+#[aliases("prune", "massdelete", "massdel")]
+#[description = "Deletes the specified amount of unpinned messages in the chat, in batches of 100. Max 5000.\nThe messages can be narrowed down with `--user`, `--bots`, `--has-attachments`, `--has-links`, `--contains` and `--regex`. Only messages matching every filter are deleted.\nUse `cancelpurge` to stop a running purge."]
+#[example = "20"]
+#[example = "50 --user @user --has-links"]
+#[example = "1000 --bots --contains \"giveaway\""]
+async fn purge(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let amount: u64 = args.single::<u64>().unwrap_or(0);
+
+    if amount < 1 {
+        return send_err_titled(
+            ctx,
+            msg,
+            "No messages!",
+            "Could you stop trying to purge thin air?",
+        )
+        .await;
+    } else if amount > MAX_PURGE {
+        return send_err_titled(
+            ctx,
+            msg,
+            "Too many messages!",
+            format!("Please keep the amount of messages to purge somewhat manageable. The maximum amount is {MAX_PURGE}."),
+        )
+        .await;
+    }
+    let filters = match parse_purge_filters(&mut args) {
+        Ok(f) => f,
+        Err(e) => return send_err_titled(ctx, msg, "Invalid filter!", e).await,
+    };
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut botdata = ctx.data.write().await;
+        let purges = botdata.entry::<ZweiPurges>().or_default();
+        if purges.contains_key(&msg.channel_id.0) {
+            drop(botdata);
+            return send_err_titled(
+                ctx,
+                msg,
+                "Already purging!",
+                "I'm still busy purging this channel. Use `cancelpurge` if you want me to stop.",
+            )
+            .await;
+        }
+        purges.insert(msg.channel_id.0, cancelled.clone());
+    }
+    // Log guilds purging messages. Will be checked when ratelimits are hit.
+    log::info!(
+        "Purging {amount} messages in guild {} ({})",
+        msg.guild(ctx).unwrap().id.0,
+        msg.guild(ctx).unwrap().name
+    );
+
+    let res = run_purge(ctx, msg, amount, &filters, &cancelled).await;
+    if let Some(purges) = ctx.data.write().await.get_mut::<ZweiPurges>() {
+        purges.remove(&msg.channel_id.0);
+    }
+    res
+}
+
+/// # run_purge
+/// Does the actual work for `purge`. Messages are fetched and deleted 100 at a
+/// time, keeping a progress embed up to date in between. Messages too old to
+/// be bulk deleted are deleted one by one instead, which is a lot slower.
+async fn run_purge(
+    ctx: &Context,
+    msg: &Message,
+    amount: u64,
+    filters: &[PurgeFilter],
+    cancelled: &AtomicBool,
+) -> CommandResult {
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    let mut progress = msg
+        .channel_id
+        .send_message(ctx, |m| {
+            m.embed(|e| {
+                e.color(color)
+                    .title("Purging")
+                    .description(format!("Looking through the last {amount} messages..."))
+            })
+        })
+        .await?;
+
+    let bulk_cutoff = Utc::now().timestamp() - BULK_DELETE_AGE;
+    let mut matched = vec![0; filters.len()];
+    let (mut scanned, mut deleted, mut pinned, mut failed) = (0, 0, 0, 0);
+    let mut before = msg.id;
+    let mut problem = None;
+    while scanned < amount && !cancelled.load(Ordering::Relaxed) {
+        let page = match msg
+            .channel_id
+            .messages(&ctx.http, |m| {
+                m.before(before).limit((amount - scanned).min(100))
+            })
+            .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                problem = Some(failure_reason(&e));
+                break;
+            }
+        };
+        // Messages come in newest first, so the last one is where to continue.
+        match page.last() {
+            Some(last) => before = last.id,
+            None => break,
+        };
+        scanned += page.len() as u64;
+
+        let (mut bulk, mut single) = (Vec::new(), Vec::new());
+        for m in page
+            .iter()
+            .filter(|m| filter_matches(filters, &mut matched, m))
+        {
+            if m.pinned {
+                pinned += 1;
+            } else if m.timestamp.unix_timestamp() > bulk_cutoff {
+                bulk.push(m.id);
+            } else {
+                single.push(m.id);
+            }
+        }
+        if !bulk.is_empty() {
+            if let Err(e) = msg.channel_id.delete_messages(&ctx.http, &bulk).await {
+                problem = Some(failure_reason(&e));
+                break;
+            }
+            deleted += bulk.len();
+        }
+        for id in single {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            match msg.channel_id.delete_message(&ctx.http, id).await {
+                Ok(_) => deleted += 1,
+                Err(e) => {
+                    log::warn!("Couldn't delete message {} while purging\n\t{e}", id.0);
+                    failed += 1;
+                }
+            }
+        }
+
+        let _ = progress
+            .edit(ctx, |m| {
+                m.embed(|e| {
+                    e.color(color).title("Purging").description(format!(
+                        "Deleted {deleted} messages so far, {scanned} out of {amount} checked.\nUse `cancelpurge` to stop me."
+                    ))
+                })
+            })
+            .await;
+    }
+
+    let stopped = cancelled.load(Ordering::Relaxed);
+    let summary: String = filters
+        .iter()
+        .zip(matched)
+        .map(|(f, n)| {
+            format!(
+                "\n{} matched {n} message{}",
+                f.describe(),
+                if n != 1 { "s" } else { "" }
+            )
+        })
+        .collect();
+    let mut report = match (deleted, pinned) {
+        (0, 0) if !filters.is_empty() => {
+            format!("None of the last {scanned} messages matched all filters.")
+        }
+        (0, 0) => "There was nothing to delete.".to_owned(),
+        (0, 1) => "That message is pinned. I can't delete it.".to_owned(),
+        (0, _) => "Those messages are all pinned. I can't delete them.".to_owned(),
+        (1, 0) if scanned == 1 => {
+            "I purged the last message. _You could've done that faster manually._".to_owned()
+        }
+        (n, 0) if filters.is_empty() && n as u64 == scanned => {
+            format!("I purged the last {n} messages.")
+        }
+        (n, 0) => format!("I purged {n} out of the last {scanned} messages."),
+        (n, 1) => format!("I purged {n} out of the last {scanned} messages. One was pinned."),
+        (n, p) => format!("I purged {n} out of the last {scanned} messages. {p} were pinned."),
+    };
+    if failed > 0 {
+        report.push_str(&format!("\n{failed} messages couldn't be deleted."));
+    }
+    if let Some(why) = &problem {
+        report.push_str(&format!("\nI had to stop early, {why}."));
+    }
+    report.push_str(&summary);
+    let title = match (stopped, &problem) {
+        (true, _) => "Purge cancelled",
+        (_, Some(_)) => "Purge interrupted",
+        _ => "Purge finished",
+    };
+    let _ = progress
+        .edit(ctx, |m| {
+            m.embed(|e| e.color(color).title(title).description(&report))
+        })
+        .await;
+
+    if deleted > 0 {
+        log_case(
+            ctx,
+            msg.guild_id.unwrap_or_default(),
+            msg.author.id,
+            msg.channel_id.0,
+            "purge",
+            &format!("Deleted {deleted} messages"),
+        )
+        .await;
+    }
+    Ok(())
+}
+
+#[command]
+#[required_permissions("MANAGE_MESSAGES")]
+#[only_in("guilds")]
+#[num_args(0)]
+#[aliases("stoppurge")]
+#[description = "Stops a purge running in this channel after the current batch."]
+async fn cancelpurge(ctx: &Context, msg: &Message) -> CommandResult {
+    let running = {
+        let botdata = ctx.data.read().await;
+        botdata
+            .get::<ZweiPurges>()
+            .and_then(|purges| purges.get(&msg.channel_id.0))
+            .map(|flag| flag.store(true, Ordering::Relaxed))
+            .is_some()
+    };
+    if running {
+        send_ok(
+            ctx,
+            msg,
+            "Cancelling purge",
+            "I'll stop purging once I'm done with this batch.",
+        )
+        .await
+    } else {
+        send_err(ctx, msg, "I'm not purging anything in this channel.").await
+    }
+}
+
+#[command]
+#[required_permissions("KICK_MEMBERS")]
+#[min_args(1)]
+#[description = "Kicks a member from the server. Optionally with a reason."]
+#[aliases("remove", "prod", "eject")]
+async fn kick(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+
+    if !guard(ctx, msg, mem_id, "kick").await? {
+        return Ok(());
+    }
+
+    let fullname = get_name(msg, ctx, mem_id).await?;
+    args.advance();
+    let reason = args.remains().unwrap_or("You know what you did!");
+
+    if let Err(e) = kick_member(ctx, msg.guild_id.unwrap_or_default(), mem_id, reason).await {
+        let txt = failure_reason(&e);
+        return send_err(ctx, msg, format!("I can't kick {fullname}, {txt}.")).await;
+    }
+    let guild = msg.guild_id.unwrap_or_default();
+    log_case(ctx, guild, msg.author.id, mem_id.0, "kick", reason).await;
+    send_ok(
+        ctx,
+        msg,
+        "User kicked.",
+        format!("I sent {fullname} away. Be careful if they return."),
+    )
+    .await
+}
+
+#[command]
+#[required_permissions("BAN_MEMBERS")]
+#[min_args(1)]
+#[aliases("yeet", "lostblue")]
+#[description = "Bans a member from the server. Optionally takes the amount of days of messages to delete (max 7), a duration for temporary bans and a reason."]
+#[example = "@user 7d Take a break."]
+#[example = "@user 1 Spamming"]
+async fn ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+
+    if !guard(ctx, msg, mem_id, "ban").await? {
+        return Ok(());
+    }
+
+    let fullname = get_name(msg, ctx, mem_id).await?;
+    args.advance();
+    let days = match args.parse::<u8>() {
+        Ok(days) => {
+            args.advance();
+            days
+        }
+        Err(_) => 0,
+    };
+    let duration = args.current().and_then(parse_duration);
+    if duration.is_some() {
+        args.advance();
+    }
+    if duration.is_some_and(|secs| secs > MAX_DELAY) {
+        return send_err(
+            ctx,
+            msg,
+            "I can't keep track of anything longer than a year.",
+        )
+        .await;
+    }
+    let reason = args.remains().unwrap_or("You know what you did!");
+
+    let realdays: u8 = if days > 7 {
+        send_err_titled(
+            ctx,
+            msg,
+            "I can't change history",
+            "and Discord doesn't allow deleting more than 7 days when banning.\nDefaulting to that instead.",
+        )
+        .await?;
+        7
+    } else {
+        days
+    };
+    if let Err(e) = ban_member(
+        ctx,
+        msg.guild_id.unwrap_or_default(),
+        mem_id,
+        realdays,
+        duration,
+        reason,
+    )
+    .await
+    {
+        let txt = failure_reason(&e);
+        return send_err(ctx, msg, format!("I can't ban {fullname}, {txt}.")).await;
+    }
+    let guild = msg.guild_id.unwrap_or_default();
+    match duration {
+        Some(secs) => {
+            let reason = format!("{reason} (for {})", fmt_duration(secs));
+            log_case(ctx, guild, msg.author.id, mem_id.0, "tempban", &reason).await
+        }
+        None => log_case(ctx, guild, msg.author.id, mem_id.0, "ban", reason).await,
+    };
+    match duration {
+        Some(secs) => {
+            send_ok(
+                ctx,
+                msg,
+                "User banned.",
+                format!(
+                    "I sent {fullname} off to Lost Blue. They'll be back in {}.",
+                    fmt_duration(secs)
+                ),
+            )
+            .await
+        }
+        None => {
+            send_ok(
+                ctx,
+                msg,
+                "User banned.",
+                format!("I sent {fullname} off to Lost Blue. You won't see them again."),
+            )
+            .await
+        }
+    }
+}
+
+#[command]
+#[required_permissions("BAN_MEMBERS")]
+#[min_args(1)]
+#[aliases("pardonban", "unyeet")]
+#[description = "Lifts a ban, optionally with a reason. Takes an ID or a name#discriminator from the ban list."]
+#[example = "123456789012345678 Appeal accepted"]
+#[example = "Someone#1234"]
+async fn unban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild = msg.guild_id.unwrap_or_default();
+    let needle = args.current().unwrap_or_default().to_owned();
+    let bans = guild.bans(ctx).await?;
+    let found = match args.parse::<UserId>() {
+        Ok(id) => bans.iter().find(|b| b.user.id == id),
+        Err(_) => {
+            let needle = needle.to_lowercase();
+            bans.iter().find(|b| {
+                format!("{}#{}", b.user.name, b.user.discriminator).to_lowercase() == needle
+            })
+        }
+    };
+    let user = match found {
+        Some(ban) => ban.user.clone(),
+        None => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "Not banned!",
+                format!(
+                    "I couldn't find `{}` in this server's ban list.",
+                    sanitize_txt(&needle)
+                ),
+            )
+            .await;
+        }
+    };
+    args.advance();
+    let reason = args
+        .remains()
+        .unwrap_or("You've been given another chance.");
+
+    if !guard(ctx, msg, user.id, "unban").await? {
+        return Ok(());
+    }
+    let fullname = format!("{}#{}", user.name, user.discriminator);
+    if let Err(e) = unban_member(ctx, guild, user.id, reason).await {
+        let txt = failure_reason(&e);
+        return send_err(ctx, msg, format!("I can't unban {fullname}, {txt}.")).await;
+    }
+    log_case(ctx, guild, msg.author.id, user.id.0, "unban", reason).await;
+    send_ok(
+        ctx,
+        msg,
+        "User unbanned.",
+        format!("I brought {fullname} back from Lost Blue. Let's hope they behave."),
+    )
+    .await
+}
+
+#[command]
+#[required_permissions("BAN_MEMBERS")]
+#[min_args(1)]
+#[aliases("cleanban", "wipe")]
+#[description = "Bans and immediately unbans a member, wiping their recent messages. Optionally takes the amount of days of messages to delete (default 1, max 7) and a reason."]
+#[example = "@user 2 Spam"]
+async fn softban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+    if !guard(ctx, msg, mem_id, "softban").await? {
+        return Ok(());
+    }
+
+    let fullname = get_name(msg, ctx, mem_id).await?;
+    args.advance();
+    let days = match args.parse::<u8>() {
+        Ok(days) => {
+            args.advance();
+            days.clamp(1, 7)
+        }
+        Err(_) => 1,
+    };
+    let reason = args.remains().unwrap_or("You know what you did!");
+    let guild = msg.guild_id.unwrap_or_default();
+
+    notify_member(
+        ctx,
+        guild,
+        mem_id,
+        "kicked and had your recent messages removed in",
+        reason,
+    )
+    .await;
+    if let Err(e) = guild.ban_with_reason(ctx, mem_id, days, reason).await {
+        let txt = failure_reason(&e);
+        return send_err(ctx, msg, format!("I can't softban {fullname}, {txt}.")).await;
+    }
+    if let Err(e) = ctx
+        .http
+        .remove_ban(guild.0, mem_id.0, Some("Softban, lifting the ban again"))
+        .await
+    {
+        log::error!(
+            "Softban of {} in {} left them banned!\n\t{e}",
+            mem_id.0,
+            guild.0
+        );
+        return send_err(
+            ctx,
+            msg,
+            format!("I wiped {fullname}'s messages, but couldn't lift the ban again. Please unban them yourself."),
+        )
+        .await;
+    }
+    log_case(ctx, guild, msg.author.id, mem_id.0, "softban", reason).await;
+    send_ok(
+        ctx,
+        msg,
+        "User softbanned.",
+        format!(
+            "I sent {fullname} away and cleaned up {} worth of their messages. They can come back if they want.",
+            fmt_duration(days as u64 * 86400)
+        ),
+    )
+    .await
+}
+
+/// The most users a single `massban` will go through.
+const MAX_MASSBAN: usize = 500;
+
+#[command]
+#[required_permissions("BAN_MEMBERS")]
+#[min_args(1)]
+#[aliases("raidban")]
+#[description = "Bans a list of users at once, for cleaning up after raids. Takes user IDs or mentions, and `--joined-after` to include everyone who joined within the given time. Optionally takes `--days` of messages to delete (max 7) and a `--reason`, which has to come last. I'll ask for confirmation first."]
+#[example = "123456789012345678 234567890123456789 --days 1 --reason Raid"]
+#[example = "--joined-after 15m --reason Raid"]
+async fn massban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild = msg.guild_id.unwrap_or_default();
+    let mut targets: Vec<UserId> = Vec::new();
+    let mut days = 0;
+    let mut reason = "Raid cleanup";
+    let mut joined_after = None;
+    while !args.is_empty() {
+        let word = args.single::<String>().unwrap_or_default();
+        match word.to_lowercase().as_str() {
+            "--days" => match args.single::<u8>() {
+                Ok(d) => days = d.min(7),
+                Err(_) => {
+                    return send_err(
+                        ctx,
+                        msg,
+                        "`--days` needs the amount of days of messages to delete.",
+                    )
+                    .await;
+                }
+            },
+            "--joined-after" => match args
+                .single::<String>()
+                .ok()
+                .and_then(|d| parse_duration(&d))
+            {
+                Some(secs) => joined_after = Some(secs),
+                None => {
+                    return send_err(
+                        ctx,
+                        msg,
+                        "`--joined-after` needs a duration, like `15m` or `2h`.",
+                    )
+                    .await;
+                }
+            },
+            "--reason" => {
+                reason = args.rest();
+                break;
+            }
+            _ => match word.parse::<UserId>() {
+                Ok(id) => targets.push(id),
+                Err(_) => {
+                    return send_err(
+                        ctx,
+                        msg,
+                        format!("`{}` isn't a user I can ban.", sanitize_txt(&word)),
+                    )
+                    .await;
+                }
+            },
+        }
+    }
+    if let Some(secs) = joined_after {
+        let since = Utc::now().timestamp() - secs as i64;
+        if let Some(g) = guild.to_guild_cached(ctx) {
+            targets.extend(
+                g.members
+                    .values()
+                    .filter(|m| m.joined_at.is_some_and(|j| j.unix_timestamp() >= since))
+                    .map(|m| m.user.id),
+            );
+        }
+    }
+    targets.sort_unstable();
+    targets.dedup();
+    if targets.is_empty() {
+        return send_err_titled(
+            ctx,
+            msg,
+            "No targets found!",
+            "There's nobody matching that for me to ban.",
+        )
+        .await;
+    } else if targets.len() > MAX_MASSBAN {
+        return send_err_titled(
+            ctx,
+            msg,
+            "Too many targets!",
+            format!(
+                "That would ban {} users, I'll only do up to {MAX_MASSBAN} at once.",
+                targets.len()
+            ),
+        )
+        .await;
+    }
+
+    let mut allowed = Vec::with_capacity(targets.len());
+    let mut err_lines = Vec::new();
+    for target in targets {
+        match check(ctx, guild, Some(msg.author.id), target).await {
+            Ok(()) => allowed.push(target),
+            Err(why) => err_lines.push(format!("+ <@{}>: {}", target.0, why.explain("ban"))),
+        }
+    }
+    if !allowed.is_empty() {
+        let question = format!(
+            "I'm about to ban {} user{}{}.\nReason: {}",
+            allowed.len(),
+            if allowed.len() != 1 { "s" } else { "" },
+            match days {
+                0 => String::new(),
+                d => format!(
+                    " and delete {} worth of their messages",
+                    fmt_duration(d as u64 * 86400)
+                ),
+            },
+            sanitize_txt(reason)
+        );
+        if !confirm(ctx, msg, "Mass ban", question).await? {
+            return Ok(());
+        }
+    }
+
+    let mut ok_lines = Vec::with_capacity(allowed.len());
+    for target in allowed {
+        match ban_member(ctx, guild, target, days, None, reason).await {
+            Ok(()) => {
+                log_case(ctx, guild, msg.author.id, target.0, "ban", reason).await;
+                ok_lines.push(format!("+ <@{0}> (`{0}`)", target.0));
+            }
+            Err(e) => err_lines.push(format!("+ <@{}>: {}", target.0, failure_reason(&e))),
+        }
+    }
+    match ok_lines.len() {
+        0 => (),
+        1 => {
+            send_ok(
+                ctx,
+                msg,
+                "A user was banned",
+                format!("I banned the following user:\n{}", ok_lines[0]),
+            )
+            .await?
+        }
+        n => {
+            send_ok(
+                ctx,
+                msg,
+                format!("{n} users were banned"),
+                fit_embed(&ok_lines),
+            )
+            .await?
+        }
+    };
+    match err_lines.len() {
+        0 => Ok(()),
+        1 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "A ban failed",
+                format!("I couldn't ban the following user:\n{}", err_lines[0]),
+            )
+            .await
+        }
+        n => send_err_titled(ctx, msg, format!("{n} bans failed"), fit_embed(&err_lines)).await,
+    }
+}
+
+#[command]
+#[required_permissions("MODERATE_MEMBERS")]
+#[min_args(1)]
+#[aliases("shush", "silence")]
+#[description = "Mutes a member, optionally for a duration and with a reason. Durations up to 28 days use Discord's timeouts, anything else uses the muted role set with `muterole`."]
+#[example = "@user 30m Cool off for a bit."]
+#[example = "@user Until further notice."]
+async fn mute(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+    if !guard(ctx, msg, mem_id, "mute").await? {
+        return Ok(());
+    }
+
+    let fullname = get_name(msg, ctx, mem_id).await?;
+    args.advance();
+    let duration = args.current().and_then(parse_duration);
+    if duration.is_some() {
+        args.advance();
+    }
+    if duration.is_some_and(|secs| secs > MAX_DELAY) {
+        return send_err(
+            ctx,
+            msg,
+            "I can't keep track of anything longer than a year.",
+        )
+        .await;
+    }
+    let reason = args.remains().unwrap_or("You know what you did!");
+    let guild = msg.guild_id.unwrap_or_default();
+
+    let muterole = {
+        let conn = &match db_conn(ctx, msg, "mute a user").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_mute_role(conn, guild.0).await?
+    };
+
+    let res = match duration {
+        Some(secs) if secs <= MAX_TIMEOUT => {
+            match timeout_member(ctx, guild, mem_id, secs, reason).await {
+                Err(e) if muterole != 0 => {
+                    log::warn!(
+                        "Timeout failed in {}, falling back to the muted role\n\t{e}",
+                        guild.0
+                    );
+                    mute_with_role(ctx, guild, mem_id, RoleId(muterole), duration, reason).await
+                }
+                res => res,
+            }
+        }
+        _ if muterole != 0 => {
+            mute_with_role(ctx, guild, mem_id, RoleId(muterole), duration, reason).await
+        }
+        _ => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No muted role!",
+                "Timeouts need a duration of at most 28 days. For anything else, please set a muted role with `muterole` first.",
+            )
+            .await;
+        }
+    };
+    if let Err(e) = res {
+        let txt = failure_reason(&e);
+        return send_err(ctx, msg, format!("I can't mute {fullname}, {txt}.")).await;
+    }
+    let reason = match duration {
+        Some(secs) => format!("{reason} (for {})", fmt_duration(secs)),
+        None => reason.to_owned(),
+    };
+    log_case(ctx, guild, msg.author.id, mem_id.0, "mute", &reason).await;
+    send_ok(
+        ctx,
+        msg,
+        "User muted.",
+        match duration {
+            Some(secs) => format!("{fullname} will be quiet for {}.", fmt_duration(secs)),
+            None => format!("{fullname} will be quiet until someone unmutes them."),
+        },
+    )
+    .await
+}
+
+#[command]
+#[required_permissions("MODERATE_MEMBERS")]
+#[min_args(1)]
+#[aliases("untimeout")]
+#[description = "Lifts a member's timeout or mute, optionally with a reason."]
+#[example = "@user"]
+async fn unmute(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+
+    if !guard(ctx, msg, mem_id, "unmute").await? {
+        return Ok(());
+    }
+    let fullname = get_name(msg, ctx, mem_id).await?;
+    args.advance();
+    let reason = args
+        .remains()
+        .unwrap_or("You've been given another chance.");
+
+    let guild = msg.guild_id.unwrap_or_default();
+    match unmute_member(ctx, guild, mem_id, reason).await {
+        Ok(true) => {
+            log_case(ctx, guild, msg.author.id, mem_id.0, "unmute", reason).await;
+            send_ok(
+                ctx,
+                msg,
+                "User unmuted.",
+                format!("{fullname} can talk again."),
+            )
+            .await
+        }
+        Ok(false) => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Not muted!",
+                format!("{fullname} wasn't muted to begin with."),
+            )
+            .await
+        }
+        Err(e) => {
+            let txt = failure_reason(&e);
+            send_err(ctx, msg, format!("I can't unmute {fullname}, {txt}.")).await
+        }
+    }
+}
+
+#[command]
+#[required_permissions("MANAGE_NICKNAMES")]
+#[min_args(1)]
+#[aliases("nickname", "rename")]
+#[description = "Changes a member's nickname. Leave the name out to reset it to their username."]
+#[example = "@user Zwei's Biggest Fan"]
+#[example = "@user"]
+async fn nick(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+
+    if !guard(ctx, msg, mem_id, "rename").await? {
+        return Ok(());
+    }
+    let fullname = get_name(msg, ctx, mem_id).await?;
+    args.advance();
+    let name = args.remains().unwrap_or_default().trim();
+    if name.chars().count() > MAX_NICK {
+        return send_err(
+            ctx,
+            msg,
+            format!("Nicknames can't be longer than {MAX_NICK} characters."),
+        )
+        .await;
+    }
+
+    let guild = msg.guild_id.unwrap_or_default();
+    let reason = match name {
+        "" => format!("Reset the nickname of `{}`", fullname.replace('`', "'")),
+        _ => format!("Renamed from `{}`", fullname.replace('`', "'")),
+    };
+    if let Err(e) = rename_member(ctx, guild, mem_id, name, &reason).await {
+        let txt = failure_reason(&e);
+        return send_err(ctx, msg, format!("I can't rename {fullname}, {txt}.")).await;
+    }
+    log_case(ctx, guild, msg.author.id, mem_id.0, "nick", &reason).await;
+    let txt = match name {
+        "" => format!("{fullname} goes by their username again."),
+        _ => format!("{fullname} is now known as {}.", sanitize_txt(name)),
+    };
+    send_ok(ctx, msg, "User renamed.", txt).await
+}
+
+#[command]
+#[required_permissions("MANAGE_NICKNAMES")]
+#[max_args(1)]
+#[description = "Strips the symbols people put in front of their name to get to the top of the member list. Dehoists everyone if no member is given."]
+#[example = "@user"]
+async fn dehoist(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild = msg.guild_id.unwrap_or_default();
+    let rules = match names::rules_for(ctx, guild).await {
+        Some(rules) => rules,
+        None => return send_err(ctx, msg, "I couldn't load the name rules.").await,
+    };
+
+    let members: Vec<Member> = if args.is_empty() {
+        match guild.to_guild_cached(ctx) {
+            Some(g) => g
+                .members
+                .values()
+                .filter(|m| !m.user.bot && dehoisted(&m.display_name()).is_some())
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    } else {
+        let mem_id = args.parse::<UserId>().unwrap_or_default();
+        if !guard(ctx, msg, mem_id, "dehoist").await? {
+            return Ok(());
+        }
+        vec![guild.member(ctx, mem_id).await?]
+    };
+    if members.is_empty() {
+        return send_ok(ctx, msg, "Nobody to dehoist", "Nobody's name is hoisted.").await;
+    }
+
+    let mut ok_lines = Vec::new();
+    let mut err_lines = Vec::new();
+    for member in members {
+        let name = member.display_name();
+        let new = match rules.dehoist(&name) {
+            Some(new) => new,
+            None => {
+                err_lines.push(format!(
+                    "+ <@{}>: their name isn't hoisted",
+                    member.user.id.0
+                ));
+                continue;
+            }
+        };
+        if let Err(why) = check(ctx, guild, Some(msg.author.id), member.user.id).await {
+            err_lines.push(format!(
+                "+ <@{}>: {}",
+                member.user.id.0,
+                why.explain("dehoist")
+            ));
+            continue;
+        }
+        match rename_member(ctx, guild, member.user.id, &new, "Dehoisted").await {
+            Ok(()) => ok_lines.push(format!(
+                "+ {} → {}",
+                sanitize_txt(&name),
+                sanitize_txt(&new)
+            )),
+            Err(e) => err_lines.push(format!("+ <@{}>: {}", member.user.id.0, failure_reason(&e))),
+        }
+    }
+    match ok_lines.len() {
+        0 => (),
+        1 => {
+            send_ok(
+                ctx,
+                msg,
+                "A name was dehoisted",
+                format!("I renamed the following member:\n{}", ok_lines[0]),
+            )
+            .await?
+        }
+        n => {
+            send_ok(
+                ctx,
+                msg,
+                format!("{n} names were dehoisted"),
+                fit_embed(&ok_lines),
+            )
+            .await?
+        }
+    };
+    match err_lines.len() {
+        0 => Ok(()),
+        1 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Couldn't dehoist",
+                format!("I couldn't rename the following member:\n{}", err_lines[0]),
+            )
+            .await
+        }
+        n => {
+            send_err_titled(
+                ctx,
+                msg,
+                format!("{n} names couldn't be dehoisted"),
+                fit_embed(&err_lines),
+            )
+            .await
+        }
+    }
+}
+
+#[command]
+#[required_permissions("MANAGE_CHANNELS")]
+#[aliases("lockdown")]
+#[description = "Stops everyone from talking in a channel, this one if none is given. Use `--all` to lock every text channel. Optionally takes a duration after which I unlock it again, and a reason."]
+#[example = "#general 30m Cooling down"]
+#[example = "--all Raid in progress"]
+async fn lock(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild = msg.guild_id.unwrap_or_default();
+    let channels: Vec<ChannelId> = match args.current() {
+        Some(all) if all.eq_ignore_ascii_case("--all") => {
+            args.advance();
+            match guild.to_guild_cached(ctx) {
+                Some(g) => g
+                    .channels
+                    .values()
+                    .filter_map(|c| c.clone().guild())
+                    .filter(|c| matches!(c.kind, ChannelType::Text | ChannelType::News))
+                    .map(|c| c.id)
+                    .collect(),
+                None => {
+                    return send_err(
+                        ctx,
+                        msg,
+                        "I can't see the channels of this server right now.",
+                    )
+                    .await
+                }
+            }
+        }
+        Some(_) => match args.parse::<ChannelId>() {
+            Ok(c) => {
+                if guild_channel(ctx, guild, c).await.is_none() {
+                    return send_err(ctx, msg, "I couldn't find that channel in this server.")
+                        .await;
+                }
+                args.advance();
+                vec![c]
+            }
+            Err(_) => vec![msg.channel_id],
+        },
+        None => vec![msg.channel_id],
+    };
+    let duration = args.current().and_then(parse_duration);
+    if duration.is_some() {
+        args.advance();
+    }
+    if duration.is_some_and(|secs| secs > MAX_DELAY) {
+        return send_err(
+            ctx,
+            msg,
+            "I can't keep track of anything longer than a year.",
+        )
+        .await;
+    }
+    let reason = args.remains().unwrap_or("Channel locked");
+
+    let mut ok_lines = Vec::with_capacity(channels.len());
+    let mut err_lines = Vec::new();
+    for channel in channels {
+        match lock_channel(ctx, guild, channel).await {
+            Ok(true) => {
+                if let Some(secs) = duration {
+                    if let Err(e) = schedule(ctx, guild.0, channel.0, "unlock", secs, 0).await {
+                        log::error!("Couldn't schedule unlocking {}\n\t{e}", channel.0);
+                    }
+                }
+                log_case(ctx, guild, msg.author.id, channel.0, "lock", reason).await;
+                ok_lines.push(format!("+ <#{}>", channel.0));
+            }
+            Ok(false) => err_lines.push(format!("+ <#{}>: it's already locked", channel.0)),
+            Err(e) => {
+                log::warn!("Couldn't lock channel {}\n\t{e}", channel.0);
+                err_lines.push(format!("+ <#{}>: I'm not allowed to edit it", channel.0));
+            }
+        }
+    }
+    let until = match duration {
+        Some(secs) => format!(" for {}", fmt_duration(secs)),
+        None => " until it's unlocked".to_owned(),
+    };
+    match ok_lines.len() {
+        0 => (),
+        1 => {
+            send_ok(
+                ctx,
+                msg,
+                "Channel locked",
+                format!("Nobody can talk in {}{until}.", &ok_lines[0][2..]),
+            )
+            .await?
+        }
+        n => {
+            send_ok(
+                ctx,
+                msg,
+                format!("{n} channels locked"),
+                format!(
+                    "Nobody can talk in these channels{until}:\n{}",
+                    fit_embed(&ok_lines)
+                ),
+            )
+            .await?
+        }
+    };
+    match err_lines.len() {
+        0 => Ok(()),
+        1 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Couldn't lock channel",
+                format!("I couldn't lock the following channel:\n{}", err_lines[0]),
+            )
+            .await
+        }
+        n => {
+            send_err_titled(
+                ctx,
+                msg,
+                format!("{n} channels weren't locked"),
+                fit_embed(&err_lines),
+            )
+            .await
+        }
+    }
+}
+
+#[command]
+#[required_permissions("MANAGE_CHANNELS")]
+#[description = "Lets everyone talk in a locked channel again, this one if none is given. Use `--all` to unlock every locked channel. Optionally takes a reason."]
+#[example = "#general"]
+#[example = "--all Raid is over"]
+async fn unlock(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild = msg.guild_id.unwrap_or_default();
+    let conn = match db_conn(ctx, msg, "unlock a channel").await {
+        Ok(conn) => conn,
+        Err(res) => return res,
+    };
+    let channels: Vec<ChannelId> = match args.current() {
+        Some(all) if all.eq_ignore_ascii_case("--all") => {
+            args.advance();
+            dbx::get_locks(&conn, guild.0)
+                .await?
+                .iter()
+                .map(|l| ChannelId(l.channelid as u64))
+                .collect()
+        }
+        Some(_) => match args.parse::<ChannelId>() {
+            Ok(c) => {
+                if guild_channel(ctx, guild, c).await.is_none() {
+                    return send_err(ctx, msg, "I couldn't find that channel in this server.")
+                        .await;
+                }
+                args.advance();
+                vec![c]
+            }
+            Err(_) => vec![msg.channel_id],
+        },
+        None => vec![msg.channel_id],
+    };
+    if channels.is_empty() {
+        return send_err(ctx, msg, "There are no locked channels in this server.").await;
+    }
+    let reason = args.remains().unwrap_or("Channel unlocked");
+
+    let mut ok_lines = Vec::with_capacity(channels.len());
+    let mut err_lines = Vec::new();
+    for channel in channels {
+        match unlock_channel(&ctx.http, &conn, guild.0, channel.0).await {
+            Ok(true) => {
+                let _ = dbx::cancel_scheduled(&conn, guild.0, channel.0, "unlock").await;
+                log_case(ctx, guild, msg.author.id, channel.0, "unlock", reason).await;
+                ok_lines.push(format!("+ <#{}>", channel.0));
+            }
+            Ok(false) => err_lines.push(format!("+ <#{}>: it isn't locked", channel.0)),
+            Err(e) => {
+                log::warn!("Couldn't unlock channel {}\n\t{e}", channel.0);
+                err_lines.push(format!("+ <#{}>: I'm not allowed to edit it", channel.0));
+            }
+        }
+    }
+    match ok_lines.len() {
+        0 => (),
+        1 => {
+            send_ok(
+                ctx,
+                msg,
+                "Channel unlocked",
+                format!("Everyone can talk in {} again.", &ok_lines[0][2..]),
+            )
+            .await?
+        }
+        n => {
+            send_ok(
+                ctx,
+                msg,
+                format!("{n} channels unlocked"),
+                format!(
+                    "Everyone can talk in these channels again:\n{}",
+                    fit_embed(&ok_lines)
+                ),
+            )
+            .await?
+        }
+    };
+    match err_lines.len() {
+        0 => Ok(()),
+        1 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Couldn't unlock channel",
+                format!("I couldn't unlock the following channel:\n{}", err_lines[0]),
+            )
+            .await
+        }
+        n => {
+            send_err_titled(
+                ctx,
+                msg,
+                format!("{n} channels weren't unlocked"),
+                fit_embed(&err_lines),
+            )
+            .await
+        }
+    }
+}
+
+/// Discord doesn't allow slowmode to go above 6 hours.
+const MAX_SLOWMODE: u64 = 6 * 3600;
+
+#[command]
+#[required_permissions("MANAGE_CHANNELS")]
+#[min_args(1)]
+#[aliases("ratelimit")]
+#[description = "Sets how long people have to wait between messages in a channel, this one if none is given. Use `off` to turn it off. Add `for` and a duration to go back to the current slowmode after that time. Max 6 hours."]
+#[example = "10s"]
+#[example = "#general 1m for 2h"]
+#[example = "off"]
+async fn slowmode(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild = msg.guild_id.unwrap_or_default();
+    // A plain number is more likely the delay than a channel ID, so it's only
+    // taken as a channel if this server has one by that ID.
+    let named = match args.parse::<ChannelId>() {
+        Ok(c) => guild_channel(ctx, guild, c).await,
+        Err(_) => None,
+    };
+    let channel = match named {
+        Some(c) => {
+            args.advance();
+            c
+        }
+        None if args.current().is_some_and(|word| word.starts_with("<#")) => {
+            return send_err(ctx, msg, "I couldn't find that channel in this server.").await;
+        }
+        None => match guild_channel(ctx, guild, msg.channel_id).await {
+            Some(c) => c,
+            None => return send_err(ctx, msg, "That's not a channel in this server.").await,
+        },
+    };
+    let current = channel.rate_limit_per_user.unwrap_or(0);
+    let channel = channel.id;
+    let delay = match args.current() {
+        Some(off) if off.eq_ignore_ascii_case("off") || off == "0" => 0,
+        Some(txt) => match parse_duration(txt) {
+            Some(secs) if secs <= MAX_SLOWMODE => secs,
+            Some(_) => {
+                return send_err(ctx, msg, "Slowmode can't be longer than 6 hours.").await;
+            }
+            None => {
+                return send_err(
+                    ctx,
+                    msg,
+                    "That's not a time I understand. Try something like `10s` or `1m`.",
+                )
+                .await;
+            }
+        },
+        None => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No slowmode provided!",
+                "Please tell me how long people have to wait between messages.",
+            )
+            .await;
+        }
+    };
+    args.advance();
+    let revert_after = match args.current() {
+        Some(word) if word.eq_ignore_ascii_case("for") => {
+            args.advance();
+            match args.current().and_then(parse_duration) {
+                Some(secs) if secs <= MAX_DELAY => Some(secs),
+                Some(_) => {
+                    return send_err(
+                        ctx,
+                        msg,
+                        "I can't keep track of anything longer than a year.",
+                    )
+                    .await;
+                }
+                None => {
+                    return send_err(
+                        ctx,
+                        msg,
+                        "Please tell me how long to keep this slowmode, like `for 2h`.",
+                    )
+                    .await;
+                }
+            }
+        }
+        Some(_) => {
+            return send_err(
+                ctx,
+                msg,
+                "I only understand a duration followed by `for` and how long to keep it.",
+            )
+            .await;
+        }
+        None => None,
+    };
+
+    // A pending revert knows what the slowmode was before it was changed for a
+    // while, so that's what any new temporary slowmode should go back to.
+    let original = {
+        let conn = &match db_conn(ctx, msg, "set slowmode").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        let pending = dbx::get_pending(conn, guild.0, channel.0, "slowmode").await?;
+        pending.map_or(current, |p| p.data as u64)
+    };
+
+    if let Err(e) = channel.edit(ctx, |c| c.rate_limit_per_user(delay)).await {
+        let txt = failure_reason(&e);
+        return send_err(
+            ctx,
+            msg,
+            format!("I can't change the slowmode in <#{}>, {txt}.", channel.0),
+        )
+        .await;
+    }
+    if let Some(conn) = ctx.data.read().await.get::<ZweiDbConn>() {
+        dbx::cancel_scheduled(conn, guild.0, channel.0, "slowmode").await?;
+    }
+    let what = match delay {
+        0 => "off".to_owned(),
+        secs => fmt_duration(secs),
+    };
+    let reason = match revert_after {
+        Some(secs) => format!("Set to {what} for {}", fmt_duration(secs)),
+        None => format!("Set to {what}"),
+    };
+    log_case(ctx, guild, msg.author.id, channel.0, "slowmode", &reason).await;
+    match revert_after {
+        Some(secs) => {
+            schedule(ctx, guild.0, channel.0, "slowmode", secs, original as i64).await?;
+            send_ok(
+                ctx,
+                msg,
+                "Slowmode changed",
+                format!(
+                    "Slowmode in <#{}> is {what} for the next {}, then it goes back to {}.",
+                    channel.0,
+                    fmt_duration(secs),
+                    match original {
+                        0 => "off".to_owned(),
+                        secs => fmt_duration(secs),
+                    }
+                ),
+            )
+            .await
+        }
+        None => {
+            send_ok(
+                ctx,
+                msg,
+                "Slowmode changed",
+                format!("Slowmode in <#{}> is {what} now.", channel.0),
+            )
+            .await
+        }
+    }
+}
+
+#[command]
+#[required_permissions("MANAGE_ROLES")]
+#[max_args(1)]
+#[aliases("mutedrole")]
+#[description = "Sets the role I use to mute people for longer than timeouts allow. Use `none` to unset it, or nothing to see the current one."]
+#[example = "@Muted"]
+#[example = "none"]
+async fn muterole(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let guild_id = msg.guild_id.unwrap().0;
+    let role = match args.current() {
+        None => None,
+        Some(r) if r.eq_ignore_ascii_case("none") => Some(0),
+        Some(_) => match args.parse::<RoleId>() {
+            Ok(r) => Some(r.0),
+            Err(_) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Invalid role!",
+                    "Please give me a role mention or an ID.",
+                )
+                .await;
+            }
+        },
+    };
+    let current = {
+        let conn = &match db_conn(ctx, msg, "set the muted role").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        match role {
+            Some(r) => {
+                dbx::set_mute_role(conn, guild_id, r).await?;
+                r
+            }
+            None => dbx::get_mute_role(conn, guild_id).await?,
+        }
+    };
+    match (role, current) {
+        (None, 0) => {
+            send_ok(
+                ctx,
+                msg,
+                "Muted role",
+                "There's no muted role set, so I can only use timeouts.",
+            )
+            .await
+        }
+        (None, r) => {
+            send_ok(
+                ctx,
+                msg,
+                "Muted role",
+                format!("I'm using <@&{r}> to mute people."),
+            )
+            .await
+        }
+        (Some(_), 0) => {
+            send_ok(
+                ctx,
+                msg,
+                "Muted role unset",
+                "I'll only use timeouts from now on.",
+            )
+            .await
+        }
+        (Some(_), r) => {
+            send_ok(
+                ctx,
+                msg,
+                "Muted role set",
+                format!("From now on I'll use <@&{r}> to mute people."),
+            )
+            .await
+        }
+    }
+}
+
+#[command]
+#[required_permissions("MODERATE_MEMBERS")]
+#[min_args(1)]
+#[description = "Warns a member, optionally with a reason. I'll let them know and keep count."]
+#[example = "@user Keep it civil, please."]
+async fn warn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+
+    if !guard(ctx, msg, mem_id, "warn").await? {
+        return Ok(());
+    }
+
+    let fullname = get_name(msg, ctx, mem_id).await?;
+    args.advance();
+    let reason = args.remains().unwrap_or("You know what you did!");
+    let guild = msg.guild_id.unwrap_or_default();
+    let (count, escalation) = match warn_member(ctx, guild, mem_id, msg.author.id, reason).await {
+        Ok(res) => res,
+        Err(e) => {
+            log::error!("Failed to warn {} in {}\n\t{e}", mem_id.0, guild.0);
+            return send_err_titled(
+                ctx,
+                msg,
+                "Catastrophic failure",
+                "I couldn't store the warning.\nContact support if this keeps happening!",
+            )
+            .await;
+        }
+    };
+
+    send_ok(
+        ctx,
+        msg,
+        "User warned.",
+        format!(
+            "I gave {fullname} a warning. That's {count} warning{} so far.",
+            if count != 1 { "s" } else { "" }
+        ),
+    )
+    .await?;
 
-use crate::{dbx, get_guildname, get_name, send_err, send_err_titled, send_ok, try_dm, ZweiData};
+    if let Some(rule) = escalation {
+        match escalate(ctx, guild, mem_id, count, &rule).await {
+            Ok(done) => {
+                send_ok(
+                    ctx,
+                    msg,
+                    "Warnings escalated.",
+                    format!("{fullname} reached {count} warnings, so I {done}."),
+                )
+                .await?
+            }
+            Err(e) => {
+                let txt = failure_reason(&e);
+                send_err(
+                    ctx,
+                    msg,
+                    format!("{fullname} reached {count} warnings, but I can't act on it, {txt}."),
+                )
+                .await?
+            }
+        }
+    }
+    Ok(())
+}
 
 #[command]
-#[required_permissions("MANAGE_MESSAGES")]
-#[only_in("guilds")]
+#[required_permissions("MODERATE_MEMBERS")]
 #[num_args(1)]
-#[aliases("prune", "massdelete", "massdel")]
-#[description = "Deletes the specified amount of unpinned messages in the chat. Max 100."]
-async fn purge(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+#[aliases("warns", "infractions")]
+#[description = "Lists all warnings a member received in this server."]
+#[example = "@user"]
+async fn warnings(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     args.trimmed();
-    let amount: u64 = args.parse::<u64>().unwrap_or(0);
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
 
-    if amount < 1 {
+    if mem_id.0 == 0 {
         return send_err_titled(
             ctx,
             msg,
-            "No messages!",
-            "Could you stop trying to purge thin air?",
+            "No target provided!",
+            "Please give me a user mention or an ID to look up.",
         )
         .await;
-    } else if amount > 100 {
-        return send_err_titled(
+    }
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let (warns, cutoff) = {
+        let conn = &match db_conn(ctx, msg, "list warnings").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        (
+            dbx::get_warnings(conn, guild_id, mem_id.0).await?,
+            dbx::warn_cutoff(conn, guild_id).await?,
+        )
+    };
+
+    let fullname = get_name(msg, ctx, mem_id)
+        .await
+        .unwrap_or_else(|_| format!("<@{}>", mem_id.0));
+    if warns.is_empty() {
+        return send_ok(
             ctx,
             msg,
-            "Too many messages!",
-            "Please keep the amount of messages to purge somewhat manageable. Due to technical limitations, the maximum amount is 100.",
+            "No warnings",
+            format!("{fullname} has a clean record here. Keep it up!"),
         )
         .await;
     }
-    // Log guilds purging messages. Will be checked when ratelimits are hit.
-    log::info!(
-        "Purging {amount} messages in guild {} ({})",
-        msg.guild(ctx).unwrap().id.0,
-        msg.guild(ctx).unwrap().name
-    );
 
-    let recent_messages = msg
-        .channel_id
-        .messages(&ctx.http, |m| m.before(msg.id).limit(amount))
-        .await?;
+    let lines: Vec<String> = warns
+        .iter()
+        .map(|w| {
+            format!(
+                "`#{}` <t:{}:d> by <@{}>: {}{}",
+                w.warnid,
+                w.issued,
+                w.moderator,
+                sanitize_txt(&w.message),
+                if w.issued < cutoff {
+                    " _(expired)_"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect();
+    send_ok(
+        ctx,
+        msg,
+        format!("Warnings for {fullname}"),
+        fit_embed(&lines),
+    )
+    .await
+}
 
-    let mut num_pinned = 0;
-    let mut to_delete = Vec::with_capacity(recent_messages.len());
-    for msg in recent_messages {
-        if msg.pinned {
-            num_pinned += 1;
-        } else {
-            to_delete.push(msg.id);
+#[command]
+#[required_permissions("MODERATE_MEMBERS")]
+#[num_args(1)]
+#[aliases("unwarn", "rmwarn")]
+#[description = "Removes a single warning, using the ID shown by `warnings`."]
+#[example = "42"]
+async fn delwarn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let warnid = match args.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No warning provided!",
+                "Please give me the ID of the warning to remove. You can find it with `warnings`.",
+            )
+            .await;
         }
-    }
+    };
+    let guild_id = msg.guild_id.unwrap().0;
 
-    let reply = match (to_delete.len(), num_pinned) {
-        (0, 1) => Err("That message is pinned. I can't delete it."),
-        (0, _) => Err("Those messages are all pinned. I can't delete them."),
-        (1, 0) => Ok("the last message. _You could've done that faster manually._".to_owned()),
-        (n, 0) => Ok(format!("the last {n} messages.")),
-        (n, 1) => Ok(format!(
-            "{n} out of the last {amount} messages. The other one was pinned."
-        )),
-        (n, p) => Ok(format!(
-            "{n} out of the last {amount} messages. The other {p} were pinned."
-        )),
+    let removed = {
+        let conn = &match db_conn(ctx, msg, "remove a warning").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::remove_warning(conn, guild_id, warnid).await?
     };
 
-    match reply {
-        Ok(r) => {
-            send_ok(ctx, msg, "Purging", r).await?;
-            msg.channel_id.delete_messages(&ctx.http, to_delete).await?;
+    match removed {
+        0 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Warning not found!",
+                format!("There's no warning `#{warnid}` in this server."),
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Warning removed.",
+                format!("Warning `#{warnid}` has been forgiven and forgotten."),
+            )
+            .await
         }
-        Err(e) => send_err(ctx, msg, e).await?,
     }
-
-    Ok(())
 }
 
 #[command]
-#[required_permissions("KICK_MEMBERS")]
+#[required_permissions("MODERATE_MEMBERS")]
 #[num_args(1)]
-#[description = "Kicks a member from the server. Optionally with a reason."]
-#[aliases("remove", "prod", "eject")]
-async fn kick(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+#[aliases("clearwarnings", "pardon")]
+#[description = "Removes all warnings a member received in this server."]
+#[example = "@user"]
+async fn clearwarns(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     args.trimmed();
     let mem_id = args.parse::<UserId>().unwrap_or_default();
 
@@ -98,222 +2294,433 @@ async fn kick(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
             ctx,
             msg,
             "No target provided!",
-            "Please give me a user mention or an ID to kick.",
-        )
-        .await;
-    } else if mem_id.0 == msg.guild(ctx).unwrap().owner_id.0 {
-        return send_err_titled(
-            ctx,
-            msg,
-            "That's not possible!",
-            "I can't kick the owner off their own server.",
+            "Please give me a user mention or an ID to pardon.",
         )
         .await;
     }
-    let memrole = msg
-        .guild(ctx)
-        .unwrap()
-        .member(ctx, u64::try_from(mem_id.0)?)
-        .await?
-        .highest_role_info(ctx)
-        .unwrap();
-    let botdata = ctx.data.read().await;
-    if let Some(data) = botdata.get::<ZweiData>() {
-        let self_id = u64::try_from(*data.get("id").unwrap())?;
-        let selfrole = msg
-            .guild(ctx)
-            .unwrap()
-            .member(ctx, self_id)
-            .await?
-            .highest_role_info(ctx)
-            .unwrap();
+    let guild_id = msg.guild_id.unwrap().0;
 
-        if self_id == mem_id.0 {
-            msg.reply_ping(ctx, "<:ZweiAngery:844167326243880960>")
-                .await?;
-            return Ok(());
-        } else if selfrole.1 <= memrole.1 {
-            return send_err(
+    let removed = {
+        let conn = &match db_conn(ctx, msg, "clear warnings").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::clear_warnings(conn, guild_id, mem_id.0).await?
+    };
+
+    let fullname = get_name(msg, ctx, mem_id)
+        .await
+        .unwrap_or_else(|_| format!("<@{}>", mem_id.0));
+    match removed {
+        0 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Nothing to clear",
+                format!("{fullname} doesn't have any warnings here."),
+            )
+            .await
+        }
+        n => {
+            send_ok(
+                ctx,
+                msg,
+                "Warnings cleared.",
+                format!(
+                    "Removed {n} warning{} from {fullname}. A fresh start!",
+                    if n != 1 { "s" } else { "" }
+                ),
+            )
+            .await
+        }
+    }
+}
+
+#[command("set")]
+#[aliases("add")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(2)]
+#[max_args(3)]
+#[description = "Makes me act when someone reaches an amount of warnings. Actions are `timeout` (which needs a duration), `kick` and `ban`."]
+#[example = "3 timeout 1h"]
+#[example = "5 kick"]
+async fn set_escalation(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let threshold = match args.single::<i64>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            return send_err_titled(
                 ctx,
                 msg,
-                "I can't kick someone whose roles are equal to or higher than my own!",
+                "No threshold provided!",
+                "Please tell me how many warnings it takes before I step in.",
             )
             .await;
         }
     };
-
-    let fullname = get_name(msg, ctx, mem_id).await?;
-    args.advance();
-    let reason = args.remains().unwrap_or("You know what you did!");
-
-    let guildname = get_guildname(msg, ctx).await;
-    let _ = try_dm(
-        ctx,
-        mem_id,
-        "<:ZweiShy:844167336336031745> Sorry!",
-        format!("You were kicked from {guildname}.\nReason: {reason}"),
-    )
-    .await;
-
-    if let Err(e) = msg
-        .guild_id
-        .unwrap_or_default()
-        .kick_with_reason(ctx, mem_id, reason)
-        .await
+    let action = args.single::<String>()?.to_lowercase();
+    let duration = match (action.as_str(), args.current().map(parse_duration)) {
+        ("timeout", Some(Some(secs))) if secs <= 28 * 86400 => secs as i64,
+        ("timeout", _) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "Invalid duration!",
+                "Timeouts need a duration like `30m` or `1h`, and Discord won't go beyond 28 days.",
+            )
+            .await;
+        }
+        ("kick", _) | ("ban", _) => 0,
+        _ => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "Unknown action!",
+                "I can only `timeout`, `kick` or `ban` people for their warnings.",
+            )
+            .await;
+        }
+    };
+    let guild_id = msg.guild_id.unwrap().0;
     {
-        let txt = match e {
-            Error::Model(ModelError::InvalidPermissions(missing_perms)) => {
-                format!("please provide me with the `{missing_perms}` permission")
-            }
-            _ => "the provided reason was too long".to_owned(),
+        let conn = &match db_conn(ctx, msg, "set an escalation").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
-        return send_err(ctx, msg, format!("I can't kick {fullname}, {txt}.")).await;
+        dbx::set_escalation(conn, guild_id, threshold, &action, duration).await?;
     }
     send_ok(
         ctx,
         msg,
-        "User kicked.",
-        format!("I sent {fullname} away. Be careful if they return."),
+        "Escalation set",
+        format!(
+            "Once someone reaches {threshold} warning{}, I'll {}.",
+            if threshold != 1 { "s" } else { "" },
+            describe_escalation(&action, duration)
+        ),
     )
     .await
 }
 
-#[command]
-#[required_permissions("BAN_MEMBERS")]
-#[min_args(1)]
-#[aliases("yeet", "lostblue")]
-async fn ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+#[command("remove")]
+#[aliases("delete", "-")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Makes me stop acting when someone reaches this amount of warnings."]
+#[example = "3"]
+async fn remove_escalation(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     args.trimmed();
-    let mem_id = args.parse::<UserId>().unwrap_or_default();
+    let threshold = match args.parse::<i64>() {
+        Ok(n) => n,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No threshold provided!",
+                "Please tell me which amount of warnings to stop acting on.",
+            )
+            .await;
+        }
+    };
+    let guild_id = msg.guild_id.unwrap().0;
+    let removed = {
+        let conn = &match db_conn(ctx, msg, "remove an escalation").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::remove_escalation(conn, guild_id, threshold).await?
+    };
+    match removed {
+        0 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Escalation not found!",
+                format!("Nothing happens at {threshold} warnings in this server."),
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Escalation removed",
+                format!("I'll no longer step in at {threshold} warnings."),
+            )
+            .await
+        }
+    }
+}
 
-    if mem_id.0 == 0 {
-        return send_err_titled(
-            ctx,
-            msg,
-            "No target provided!",
-            "Please give me a user mention or an ID to ban.",
+#[command("list")]
+#[required_permissions("MODERATE_MEMBERS")]
+#[max_args(0)]
+#[description = "Shows what I'll do when people collect warnings, and when warnings expire."]
+async fn list_escalations(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let (rules, expiry) = {
+        let conn = &match db_conn(ctx, msg, "list escalations").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        (
+            dbx::get_escalations(conn, guild_id).await?,
+            dbx::get_warn_expiry(conn, guild_id).await?,
         )
-        .await;
-    } else if mem_id.0 == msg.guild(ctx).unwrap().owner_id.0 {
-        return send_err_titled(
+    };
+    let expirytxt = match expiry {
+        1.. => format!(
+            "Warnings stop counting after {}.",
+            fmt_duration(expiry as u64 * 86400)
+        ),
+        _ => "Warnings never expire.".to_owned(),
+    };
+    if rules.is_empty() {
+        return send_ok(
             ctx,
             msg,
-            "That's not possible!",
-            "I can't ban the owner from own server.",
+            "Escalations for this server",
+            format!("I won't step in no matter how many warnings people collect.\n{expirytxt}"),
         )
         .await;
     }
-    let memrole = msg
-        .guild(ctx)
-        .unwrap()
-        .member(ctx, mem_id.0)
-        .await?
-        .highest_role_info(ctx)
-        .unwrap();
-    let botdata = ctx.data.read().await;
-    if let Some(data) = botdata.get::<ZweiData>() {
-        let self_id = u64::try_from(*data.get("id").unwrap())?;
-        let selfrole = msg
-            .guild(ctx)
-            .unwrap()
-            .member(ctx, self_id)
-            .await?
-            .highest_role_info(ctx)
-            .unwrap();
+    send_ok(
+        ctx,
+        msg,
+        "Escalations for this server",
+        format!(
+            "+ {}\n{expirytxt}",
+            rules
+                .iter()
+                .map(|r| format!(
+                    "{} warnings: {}",
+                    r.threshold,
+                    describe_escalation(&r.action, r.duration)
+                ))
+                .collect::<Vec<String>>()
+                .join("\n+ ")
+        ),
+    )
+    .await
+}
 
-        if self_id == mem_id.0 {
-            msg.reply_ping(ctx, "<:ZweiAngery:844167326243880960>")
-                .await?;
-            return Ok(());
-        } else if selfrole.1 <= memrole.1 {
-            return send_err(
+#[command("expiry")]
+#[aliases("expire")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Makes warnings stop counting towards escalations after this many days. Use 0 to keep them forever."]
+#[example = "30"]
+async fn warn_expiry(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let days = match args.parse::<i64>() {
+        Ok(n) if n >= 0 => n,
+        _ => {
+            return send_err_titled(
                 ctx,
                 msg,
-                "I can't ban someone whose roles are equal to or higher than my own!",
+                "Invalid amount of days!",
+                "Please give me the amount of days warnings should last, or 0 to keep them forever.",
             )
             .await;
         }
     };
-
-    let fullname = get_name(msg, ctx, mem_id).await?;
-    args.advance();
-    let days = args.parse::<u8>().unwrap_or(0);
-    args.advance();
-    let reason = args.remains().unwrap_or("You know what you did!");
-
-    let guildname = get_guildname(msg, ctx).await;
-    let _ = try_dm(
-        ctx,
-        mem_id,
-        "<:ZweiShy:844167336336031745> Sorry!",
-        format!("You were banned from {guildname}.\nReason: {reason}",),
-    )
-    .await;
-
-    let realdays: u8 = if days > 7 {
-        send_err_titled(
-            ctx,
-            msg,
-            "I can't change history",
-            "and Discord doesn't allow deleting more than 7 days when banning.\nDefaulting to that instead.",
-        )
-        .await?;
-        7
-    } else {
-        days
-    };
-    if let Err(e) = msg
-        .guild_id
-        .unwrap_or_default()
-        .ban_with_reason(ctx, mem_id, realdays, reason)
-        .await
+    let guild_id = msg.guild_id.unwrap().0;
     {
-        let txt = match e {
-            Error::Model(ModelError::InvalidPermissions(missing_perms)) => {
-                format!("please provide me with the `{missing_perms}` permission")
-            }
-            _ => "the provided reason was too long".to_owned(),
+        let conn = &match db_conn(ctx, msg, "set warning expiry").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
-        return send_err(ctx, msg, format!("I can't ban {fullname}, {txt}.")).await;
+        dbx::set_warn_expiry(conn, guild_id, days).await?;
     }
-    return send_ok(
+    send_ok(
         ctx,
         msg,
-        "User banned.",
-        format!("I sent {fullname} off to Lost Blue. You won't see them again."),
+        "Warning expiry set",
+        match days {
+            0 => "Warnings will keep counting forever.".to_owned(),
+            _ => format!(
+                "Warnings will stop counting after {}.",
+                fmt_duration(days as u64 * 86400)
+            ),
+        },
     )
-    .await;
+    .await
 }
 
-#[command]
-#[num_args(1)]
-async fn warn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    args.trimmed();
-    let mem_id = args.parse::<UserId>().unwrap_or_default();
-
-    if mem_id.0 == 0 {
-        return send_err_titled(
-            ctx,
-            msg,
-            "No target provided!",
-            "Please give me a user mention or an ID to warn.",
-        )
-        .await;
-    } else if mem_id.0 == msg.guild(ctx).unwrap().owner_id.0 {
-        return send_err_titled(
-            ctx,
-            msg,
-            "That's not useful!",
-            "Warning the owner has no use, even if I'd love to.",
-        )
-        .await;
+/// # describe_escalation
+/// Describes what an escalation rule does, for listing and confirming them.
+fn describe_escalation(action: &str, duration: i64) -> String {
+    match action {
+        "timeout" => format!("time them out for {}", fmt_duration(duration as u64)),
+        "kick" => "kick them".to_owned(),
+        "ban" => "ban them".to_owned(),
+        other => format!("do something I don't understand (`{other}`)"),
     }
-
-    Ok(())
 }
 
 #[group("Modtools")]
-#[commands(purge, kick, ban, warn)]
+#[commands(
+    purge,
+    cancelpurge,
+    kick,
+    ban,
+    unban,
+    softban,
+    massban,
+    mute,
+    unmute,
+    nick,
+    dehoist,
+    lock,
+    unlock,
+    slowmode,
+    muterole,
+    warn,
+    warnings,
+    delwarn,
+    clearwarns
+)]
 #[summary = "Commands for moderators and admins of a server."]
 #[only_in("guilds")]
 struct ModTools;
+
+#[group("Escalation")]
+#[commands(set_escalation, remove_escalation, list_escalations, warn_expiry)]
+#[summary = "Configure what I do when people collect warnings. Shows the current rules if no extra arguments are given."]
+#[prefixes("escalation", "escalations")]
+#[default_command(list_escalations)]
+#[only_in("guilds")]
+struct Escalation;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use serenity::framework::standard::Delimiter;
+
+    fn message(author: u64, bot: bool, content: &str, attachments: usize) -> Message {
+        let attachments: Vec<Value> = (0..attachments)
+            .map(|i| {
+                json!({
+                    "id": (i + 1).to_string(),
+                    "filename": "file.png",
+                    "size": 1,
+                    "url": "https://cdn.example/file.png",
+                    "proxy_url": "https://cdn.example/file.png",
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "id": "1",
+            "channel_id": "2",
+            "author": {
+                "id": author.to_string(),
+                "username": "someone",
+                "discriminator": "0001",
+                "avatar": null,
+                "bot": bot,
+            },
+            "content": content,
+            "timestamp": "2024-01-01T00:00:00Z",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": attachments,
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        }))
+        .unwrap()
+    }
+
+    fn filters(txt: &str) -> Result<Vec<PurgeFilter>, String> {
+        parse_purge_filters(&mut Args::new(txt, &[Delimiter::Single(' ')]))
+    }
+
+    #[test]
+    fn purge_filters_parse() {
+        assert!(filters("").unwrap().is_empty());
+        let parsed = filters("--user <@123> --bots --links --contains \"Free Nitro\"").unwrap();
+        assert_eq!(parsed.len(), 4);
+        assert!(matches!(parsed[0], PurgeFilter::User(UserId(123))));
+        assert!(matches!(&parsed[3], PurgeFilter::Contains(txt) if txt == "free nitro"));
+    }
+
+    #[test]
+    fn purge_filters_reject_bad_input() {
+        assert!(filters("--nope").is_err());
+        assert!(filters("--user").is_err());
+        assert!(filters("--user someone").is_err());
+        assert!(filters("--contains").is_err());
+        assert!(filters("--contains \"\"").is_err());
+        assert!(filters("--regex").is_err());
+        assert!(filters("--regex (").is_err());
+    }
+
+    #[test]
+    fn purge_filters_match() {
+        let user = message(5, false, "Check https://example.com out", 0);
+        let bot = message(6, true, "FREE NITRO here", 1);
+        let [by_user, bots, files, links, nitro, numbers] = [
+            "--user 5",
+            "--bots",
+            "--has-attachments",
+            "--has-links",
+            "--contains nitro",
+            "--regex \\d",
+        ]
+        .map(|f| filters(f).unwrap().remove(0));
+        assert!(by_user.matches(&user) && !by_user.matches(&bot));
+        assert!(bots.matches(&bot) && !bots.matches(&user));
+        assert!(files.matches(&bot) && !files.matches(&user));
+        assert!(links.matches(&user) && !links.matches(&bot));
+        assert!(nitro.matches(&bot) && !nitro.matches(&user));
+        assert!(!numbers.matches(&user) && !numbers.matches(&bot));
+    }
+
+    #[test]
+    fn purge_filters_need_all_to_match() {
+        let filters = filters("--bots --contains nitro").unwrap();
+        let mut matched = vec![0; filters.len()];
+        assert!(filter_matches(
+            &filters,
+            &mut matched,
+            &message(6, true, "nitro", 0)
+        ));
+        assert!(!filter_matches(
+            &filters,
+            &mut matched,
+            &message(5, false, "nitro", 0)
+        ));
+        assert!(!filter_matches(
+            &filters,
+            &mut matched,
+            &message(6, true, "hello", 0)
+        ));
+        assert_eq!(matched, vec![2, 2]);
+    }
+
+    #[test]
+    fn dehoisted_strips_leading_symbols() {
+        assert_eq!(dehoisted("!!!Bob"), Some("Bob".to_owned()));
+        assert_eq!(dehoisted(" ! Bob"), Some("Bob".to_owned()));
+        assert_eq!(dehoisted("_.-=[Bob]"), Some("Bob]".to_owned()));
+    }
+
+    #[test]
+    fn dehoisted_leaves_normal_names() {
+        assert_eq!(dehoisted(""), None);
+        assert_eq!(dehoisted("Bob"), None);
+        assert_eq!(dehoisted("Bob!"), None);
+        assert_eq!(dehoisted("0Bob"), None);
+        assert_eq!(dehoisted("Ōkami"), None);
+    }
+
+    #[test]
+    fn dehoisted_can_leave_nothing() {
+        assert_eq!(dehoisted("!!!"), Some(String::new()));
+        assert_eq!(dehoisted("   "), Some(String::new()));
+    }
+}
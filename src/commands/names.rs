@@ -0,0 +1,268 @@
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use crate::{
+    automod::compile_pattern,
+    commands::modtools::MAX_NICK,
+    db_conn, dbx, fit_embed,
+    names::{enforce_name, invalidate, rules_for},
+    sanitize_txt, send_err, send_err_titled, send_ok,
+};
+
+/// # describe
+/// Describes the name rules, one rule per line.
+fn describe(rules: &dbx::NameRules) -> String {
+    let onoff = |on: bool| if on { "on" } else { "off" };
+    format!(
+        "Names that break a rule become **{}**.\nDehoisting is {}.\nRequiring a typeable name is {}.\nBlocking zalgo names is {}.",
+        sanitize_txt(&rules.placeholder),
+        onoff(rules.dehoist),
+        onoff(rules.ascii),
+        onoff(rules.zalgo)
+    )
+}
+
+/// # parse_toggle
+/// Reads `on` or `off` (or anything that means the same).
+fn parse_toggle(args: &Args) -> Option<bool> {
+    match args.current()?.to_lowercase().as_str() {
+        "on" | "yes" | "true" | "enable" => Some(true),
+        "off" | "no" | "false" | "disable" => Some(false),
+        _ => None,
+    }
+}
+
+/// # update
+/// Changes this guild's name rules and shows the result. Shared by all
+/// commands that change a rule.
+async fn update<F>(ctx: &Context, msg: &Message, change: F) -> CommandResult
+where
+    F: FnOnce(&mut dbx::NameRules),
+{
+    let guild = msg.guild_id.unwrap();
+    let rules = {
+        let conn = &match db_conn(ctx, msg, "change name rules").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        let mut rules = dbx::get_namerules(conn, guild.0).await?;
+        change(&mut rules);
+        dbx::set_namerules(conn, guild.0, &rules).await?;
+        rules
+    };
+    invalidate(ctx, guild).await;
+    send_ok(ctx, msg, "Name rules updated", describe(&rules)).await
+}
+
+/// # toggle
+/// Shared body of the commands that turn a rule on or off.
+async fn toggle<F>(ctx: &Context, msg: &Message, args: &Args, change: F) -> CommandResult
+where
+    F: FnOnce(&mut dbx::NameRules, bool),
+{
+    match parse_toggle(args) {
+        Some(on) => update(ctx, msg, |rules| change(rules, on)).await,
+        None => send_err(ctx, msg, "Please tell me whether to turn it `on` or `off`.").await,
+    }
+}
+
+#[command("status")]
+#[aliases("show", "list")]
+#[required_permissions("MANAGE_NICKNAMES")]
+#[max_args(0)]
+#[description = "Shows the current name rules and denied patterns."]
+async fn names_status(ctx: &Context, msg: &Message) -> CommandResult {
+    let rules = match rules_for(ctx, msg.guild_id.unwrap()).await {
+        Some(rules) => rules,
+        None => return send_err(ctx, msg, "I couldn't load the name rules.").await,
+    };
+    let mut lines = vec![describe(&rules.rules)];
+    if !rules.denied.is_empty() {
+        lines.push("\nNames may not match these patterns:".to_owned());
+        lines.extend(
+            rules
+                .denied
+                .iter()
+                .map(|d| format!("`#{}` `{}`", d.id, d.pattern.replace('`', "'"))),
+        );
+    }
+    send_ok(ctx, msg, "Name rules", fit_embed(&lines)).await
+}
+
+#[command("placeholder")]
+#[aliases("fallback")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(1)]
+#[description = "Sets the name members get when their name breaks a rule."]
+#[example = "Moderated Nickname"]
+async fn names_placeholder(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let name = args.rest().trim().to_owned();
+    if name.is_empty() || name.chars().count() > MAX_NICK {
+        return send_err(
+            ctx,
+            msg,
+            format!("The placeholder needs to be between 1 and {MAX_NICK} characters."),
+        )
+        .await;
+    }
+    update(ctx, msg, |rules| rules.placeholder = name).await
+}
+
+#[command("dehoist")]
+#[aliases("hoisting")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Turns automatically stripping leading symbols from names on or off."]
+#[example = "on"]
+async fn names_dehoist(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    toggle(ctx, msg, &args, |rules, on| rules.dehoist = on).await
+}
+
+#[command("ascii")]
+#[aliases("typeable")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Turns requiring at least one regular letter or digit in names on or off, so everyone can be mentioned."]
+#[example = "on"]
+async fn names_ascii(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    toggle(ctx, msg, &args, |rules, on| rules.ascii = on).await
+}
+
+#[command("zalgo")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Turns blocking zalgo names on or off."]
+#[example = "on"]
+async fn names_zalgo(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    toggle(ctx, msg, &args, |rules, on| rules.zalgo = on).await
+}
+
+#[command("deny")]
+#[aliases("block", "+")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(1)]
+#[description = "Adds a regex names may not match. Add `(?i)` in front to ignore case."]
+#[example = "(?i)discord\\.gg"]
+async fn names_deny(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let pattern = args.rest().trim();
+    if compile_pattern("regex", pattern).is_err() {
+        return send_err(ctx, msg, "That's not a pattern I can use.").await;
+    }
+    let guild = msg.guild_id.unwrap();
+    let patternid = {
+        let conn = &match db_conn(ctx, msg, "deny a name").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::add_name_denied(conn, guild.0, pattern).await?
+    };
+    invalidate(ctx, guild).await;
+    send_ok(
+        ctx,
+        msg,
+        "Name pattern denied",
+        format!("Members with names matching this get renamed. It's pattern #{patternid}."),
+    )
+    .await
+}
+
+#[command("allow")]
+#[aliases("unblock", "-")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Removes a denied name pattern by its number."]
+#[example = "2"]
+async fn names_allow(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let patternid = match args.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No pattern provided!",
+                "Please give me the number of the pattern to remove, as shown by `names`.",
+            )
+            .await;
+        }
+    };
+    let guild = msg.guild_id.unwrap();
+    let removed = {
+        let conn = &match db_conn(ctx, msg, "allow a name").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::remove_name_denied(conn, guild.0, patternid).await?
+    };
+    invalidate(ctx, guild).await;
+    match removed {
+        0 => {
+            send_err(
+                ctx,
+                msg,
+                format!("There's no denied name pattern #{patternid} in this server."),
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Name pattern allowed",
+                format!("Names matching pattern #{patternid} are allowed again."),
+            )
+            .await
+        }
+    }
+}
+
+#[command("apply")]
+#[aliases("enforce")]
+#[required_permissions("MANAGE_GUILD")]
+#[max_args(0)]
+#[description = "Checks everyone's name against the rules right away, instead of waiting for them to change it."]
+async fn names_apply(ctx: &Context, msg: &Message) -> CommandResult {
+    let members: Vec<Member> = match msg.guild_id.unwrap().to_guild_cached(ctx) {
+        Some(g) => g.members.values().cloned().collect(),
+        None => Vec::new(),
+    };
+    let mut renamed = 0;
+    for member in &members {
+        if enforce_name(ctx, member).await {
+            renamed += 1;
+        }
+    }
+    send_ok(
+        ctx,
+        msg,
+        "Name rules applied",
+        format!(
+            "I checked {} members and renamed {renamed} of them.",
+            members.len()
+        ),
+    )
+    .await
+}
+
+#[group("Names")]
+#[commands(
+    names_status,
+    names_placeholder,
+    names_dehoist,
+    names_ascii,
+    names_zalgo,
+    names_deny,
+    names_allow,
+    names_apply
+)]
+#[summary = "Rules for display names. Members breaking them get renamed when they join or change their name. Shows the current rules if no extra arguments are given."]
+#[prefixes("names", "namerules")]
+#[default_command(names_status)]
+#[only_in("guilds")]
+struct Names;
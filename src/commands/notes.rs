@@ -0,0 +1,176 @@
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::prelude::*,
+    prelude::*,
+};
+
+use crate::{db_conn, dbx, fit_embed, get_name, sanitize_txt, send_err, send_err_titled, send_ok};
+
+/// # note_lines
+/// Renders notes one per line, for `note list` and `userinfo`.
+pub fn note_lines(notes: &[dbx::Note]) -> Vec<String> {
+    notes
+        .iter()
+        .map(|n| {
+            format!(
+                "`#{}` <t:{}:d> by <@{}>: {}",
+                n.noteid,
+                n.created,
+                n.author,
+                sanitize_txt(&n.note)
+            )
+        })
+        .collect()
+}
+
+#[command("add")]
+#[aliases("new", "+")]
+#[required_permissions("MODERATE_MEMBERS")]
+#[min_args(2)]
+#[description = "Adds a private note on a member. They won't be told about it, and it doesn't count as a warning."]
+#[example = "@user Alt of 123456789012345678"]
+async fn note_add(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+    if mem_id.0 == 0 {
+        return send_err_titled(
+            ctx,
+            msg,
+            "No target provided!",
+            "Please give me a user mention or an ID to add a note to.",
+        )
+        .await;
+    }
+    args.advance();
+    let note = args.rest().trim();
+    if note.is_empty() {
+        return send_err(ctx, msg, "What should the note say?").await;
+    }
+
+    let guild_id = msg.guild_id.unwrap().0;
+    let noteid = {
+        let conn = &match db_conn(ctx, msg, "add a note").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::add_note(conn, guild_id, mem_id.0, msg.author.id.0, note).await?
+    };
+    let fullname = get_name(msg, ctx, mem_id)
+        .await
+        .unwrap_or_else(|_| format!("<@{}>", mem_id.0));
+    send_ok(
+        ctx,
+        msg,
+        "Note added",
+        format!("I added note `#{noteid}` on {fullname}."),
+    )
+    .await
+}
+
+#[command("list")]
+#[aliases("show")]
+#[required_permissions("MODERATE_MEMBERS")]
+#[num_args(1)]
+#[description = "Shows all notes on a member."]
+#[example = "@user"]
+async fn note_list(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let mem_id = args.parse::<UserId>().unwrap_or_default();
+    if mem_id.0 == 0 {
+        return send_err_titled(
+            ctx,
+            msg,
+            "No target provided!",
+            "Please give me a user mention or an ID to look up.",
+        )
+        .await;
+    }
+
+    let guild_id = msg.guild_id.unwrap().0;
+    let notes = {
+        let conn = &match db_conn(ctx, msg, "list notes").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_notes(conn, guild_id, mem_id.0).await?
+    };
+    let fullname = get_name(msg, ctx, mem_id)
+        .await
+        .unwrap_or_else(|_| format!("<@{}>", mem_id.0));
+    if notes.is_empty() {
+        return send_ok(
+            ctx,
+            msg,
+            "No notes",
+            format!("There are no notes on {fullname}."),
+        )
+        .await;
+    }
+    send_ok(
+        ctx,
+        msg,
+        format!("Notes on {fullname}"),
+        fit_embed(&note_lines(&notes)),
+    )
+    .await
+}
+
+#[command("remove")]
+#[aliases("delete", "-")]
+#[required_permissions("MODERATE_MEMBERS")]
+#[num_args(1)]
+#[description = "Removes a note by its number."]
+#[example = "4"]
+async fn note_remove(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let noteid = match args.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            return send_err_titled(
+                ctx,
+                msg,
+                "No note provided!",
+                "Please give me the number of the note to remove, as shown by `note list`.",
+            )
+            .await;
+        }
+    };
+    let guild_id = msg.guild_id.unwrap().0;
+    let removed = {
+        let conn = &match db_conn(ctx, msg, "remove a note").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::remove_note(conn, guild_id, noteid).await?
+    };
+    match removed {
+        0 => {
+            send_err(
+                ctx,
+                msg,
+                format!("There's no note `#{noteid}` in this server."),
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Note removed",
+                format!("Note `#{noteid}` is gone."),
+            )
+            .await
+        }
+    }
+}
+
+#[group("Notes")]
+#[commands(note_add, note_list, note_remove)]
+#[summary = "Private notes staff keep on members. They're never shown to the member and don't count as warnings. Lists a member's notes if only a member is given."]
+#[prefixes("note", "notes")]
+#[default_command(note_list)]
+#[only_in("guilds")]
+struct Notes;
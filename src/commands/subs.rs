@@ -1,51 +1,192 @@
+use chrono::Utc;
 use log;
 use serenity::{
     framework::standard::{
         macros::{command, group},
         Args, CommandResult,
     },
+    futures::{stream, StreamExt},
     model::prelude::*,
     prelude::*,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    dbx::{self, ZweiDbConn},
-    send_err, send_err_titled, send_ok,
+    db_conn,
+    dbx::{self, Pool, SQLerr, ZweiDbConn},
+    fit_embed, fit_lines, fmt_duration, get_color, paginate, parse_duration, sanitize_txt,
+    send_err, send_err_titled, send_ok, tagpanel, try_dm, zwei_conf,
 };
 
+/// # MAX_DESCRIPTION
+/// The longest a tag description may be. Discord doesn't show more than this
+/// in menus.
+pub(crate) const MAX_DESCRIPTION: usize = 100;
+
+/// # TAGS_PER_PAGE
+/// How many tags `tag list` shows at once.
+const TAGS_PER_PAGE: usize = 10;
+
+/// # is_emoji_part
+/// Checks whether a character can show up in a unicode emoji, either as an
+/// emoji itself or as one of the joiners and modifiers they're built from.
+fn is_emoji_part(c: char) -> bool {
+    is_emoji_modifier(c)
+        || matches!(
+            c as u32,
+            0x00A9
+                | 0x00AE
+                | 0x203C
+                | 0x2049
+                | 0x2122
+                | 0x2139
+                | 0x2194..=0x21AA
+                | 0x231A..=0x23FF
+                | 0x24C2
+                | 0x25AA..=0x27BF
+                | 0x2934..=0x2935
+                | 0x2B05..=0x2B55
+                | 0x3030
+                | 0x303D
+                | 0x3297
+                | 0x3299
+                | 0x1F000..=0x1FAFF
+        )
+}
+
+/// # is_emoji_modifier
+/// Checks whether a character only changes the emoji before it, like the
+/// zero width joiner, variation selectors, the keycap and tag characters.
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0xE0020..=0xE007F
+    )
+}
+
+/// # is_emoji
+/// Checks whether something is an emoji, either a unicode one or a custom one
+/// as Discord sends them (`<:name:id>`). Unicode emoji have to start with an
+/// actual emoji and consist only of emoji and their modifiers, or be a keycap
+/// like 1️⃣, so Discord won't refuse them in menus.
+pub(crate) fn is_emoji(txt: &str) -> bool {
+    if txt.starts_with('<') {
+        return matches!(ReactionType::try_from(txt), Ok(ReactionType::Custom { .. }));
+    }
+    let mut chars = txt.chars();
+    match chars.next() {
+        Some('0'..='9' | '#' | '*') => matches!(chars.as_str(), "\u{20E3}" | "\u{FE0F}\u{20E3}"),
+        Some(first) => {
+            !is_emoji_modifier(first)
+                && is_emoji_part(first)
+                && txt.chars().count() <= 16
+                && chars.all(is_emoji_part)
+        }
+        None => false,
+    }
+}
+
+/// # merge_delivery
+/// Combines how someone wants to hear about two of the tags being pinged at
+/// once, so they're only notified once per ping. Asking for both anywhere
+/// gets both, and a mention wins over a DM otherwise, as the mention already
+/// lists every pinged tag.
+fn merge_delivery(a: &str, b: &str) -> String {
+    match (a, b) {
+        ("both", _) | (_, "both") => "both",
+        ("dm", "dm") => "dm",
+        _ => "channel",
+    }
+    .to_owned()
+}
+
+/// # tag_line
+/// Renders a tag as a single line for listings, with its emoji and
+/// description if it has them.
+pub(crate) fn tag_line(tag: &dbx::TagInfo) -> String {
+    let mut line = format!("+ **{}**", sanitize_txt(&tag.tagname));
+    if !tag.emoji.is_empty() {
+        line = format!("{line} {}", tag.emoji);
+    }
+    if !tag.description.is_empty() {
+        line = format!("{line}: {}", sanitize_txt(&tag.description));
+    }
+    match tag.subs {
+        1 => format!("{line} _(1 subscriber)_"),
+        n => format!("{line} _({n} subscribers)_"),
+    }
+}
+
+/// # read_description
+/// Reads a quoted description and an optional emoji after it, as used by
+/// `tag add` and `tag describe`. Custom emoji have to be from this guild, so
+/// I'm sure to be able to use them. Returns what's wrong with them otherwise.
+fn read_description(
+    ctx: &Context,
+    guild: GuildId,
+    args: &mut Args,
+) -> Result<(String, String), String> {
+    let description = args
+        .single_quoted::<String>()
+        .map(|d| d.trim().to_owned())
+        .unwrap_or_default();
+    if description.chars().count() > MAX_DESCRIPTION {
+        return Err(format!(
+            "Please keep the description under {MAX_DESCRIPTION} characters."
+        ));
+    }
+    let emoji = args.rest().trim().to_owned();
+    if !emoji.is_empty() && !is_emoji(&emoji) {
+        return Err(format!(
+            "`{}` doesn't look like an emoji to me.",
+            emoji.replace('`', "'")
+        ));
+    }
+    if let Ok(ReactionType::Custom { id, .. }) = ReactionType::try_from(emoji.as_str()) {
+        let known = guild
+            .to_guild_cached(ctx)
+            .is_none_or(|g| g.emojis.contains_key(&id));
+        if !known {
+            return Err("Please pick an emoji from this server.".to_owned());
+        }
+    }
+    Ok((description, emoji))
+}
+
 #[command("add")]
 #[aliases("create", "+")]
 #[only_in("guilds")]
 #[required_permissions("MANAGE_GUILD")]
-#[description = "Lets me notify people of certain tags when they subscribe to them. Tags are always a single word, so use dashes or underscores to avoid naming conflicts."]
+#[description = "Lets me notify people of certain tags when they subscribe to them. Tags are always a single word, so use dashes or underscores to avoid naming conflicts. To describe a tag, add it on its own with a description in quotes, optionally followed by an emoji."]
+#[example = "splatoon \"Matches, splatfests and news\" 🦑"]
+#[example = "minecraft terraria"]
 #[help_available(true)]
 async fn add_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     if args.is_empty() {
         return send_err(ctx, msg, "I can't add tags without an actual tag to add.").await;
     }
+    // A quoted second argument means a single tag with a description.
+    if args
+        .raw_quoted()
+        .nth(1)
+        .is_some_and(|arg| arg.starts_with('"'))
+    {
+        return add_described_tag(ctx, msg, args).await;
+    }
     let guild_id = msg.guild_id.unwrap().0;
+    let creator = msg.author.id.0;
     let mut ok_count: usize = args.len();
     let mut ok_list: String = String::with_capacity(args.message().len() + (args.len() * 3));
     let mut err_tags: Vec<String> = Vec::with_capacity(args.len());
     {
-        let botdata = ctx.data.read().await;
-        let conn = match botdata.get::<ZweiDbConn>() {
-            Some(conn) => conn,
-            _ => {
-                log::error!("Failed to acquire database connection object to add tags!");
-                return send_err_titled(
-                    ctx,
-                    msg,
-                    "Catastrophic failure",
-                    "Could not acquire the database connection object.\nContact support if this keeps happening!"
-                ).await;
-            }
+        let conn = &match db_conn(ctx, msg, "add tags").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
         for tag in args.iter() {
             let tagstr: String = tag?;
-            match dbx::add_tag(conn, guild_id, &tagstr.to_lowercase()).await {
-                Ok(_) => ok_list.push_str(format!("\n+ {tagstr}").as_str()),
+            match dbx::add_tag(conn, guild_id, &tagstr.to_lowercase(), "", creator, "").await {
+                Ok(1..) => ok_list.push_str(format!("\n+ {tagstr}").as_str()),
                 _ => {
                     log::warn!("Failed to add tag {tagstr} for {guild_id}");
                     ok_count -= 1;
@@ -54,6 +195,7 @@ async fn add_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
             };
         }
     }
+    tagpanel::refresh_panels(ctx, msg.guild_id).await;
     match ok_count {
         1 => {
             send_ok(
@@ -95,7 +237,7 @@ async fn add_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
                 "Tag already registered",
                 format!(
                     "{} was already registered for this server.",
-                    err_tags.get(0).unwrap()
+                    err_tags.first().unwrap()
                 ),
             )
             .await
@@ -104,6 +246,109 @@ async fn add_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
     }
 }
 
+/// # add_described_tag
+/// Adds a single tag along with a description and emoji. Used by `tag add`
+/// when it's given a quoted description.
+async fn add_described_tag(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let tagstr = args.single::<String>()?.to_lowercase();
+    let guild_id = msg.guild_id.unwrap().0;
+    let (description, emoji) = match read_description(ctx, GuildId(guild_id), &mut args) {
+        Ok(read) => read,
+        Err(why) => return send_err(ctx, msg, why).await,
+    };
+    let added = {
+        let conn = &match db_conn(ctx, msg, "add a tag").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::add_tag(
+            conn,
+            guild_id,
+            &tagstr,
+            &description,
+            msg.author.id.0,
+            &emoji,
+        )
+        .await
+    };
+    tagpanel::refresh_panels(ctx, msg.guild_id).await;
+    match added {
+        Ok(1..) => {
+            send_ok(
+                ctx,
+                msg,
+                "A new tag was added",
+                format!(
+                    "Added the following tag for subscribing to in this server:\n+ {tagstr} {emoji}\n{}",
+                    sanitize_txt(&description)
+                ),
+            )
+            .await
+        }
+        _ => {
+            log::warn!("Failed to add tag {tagstr} for {guild_id}");
+            send_err_titled(
+                ctx,
+                msg,
+                "Tag already registered",
+                format!("{tagstr} was already registered for this server."),
+            )
+            .await
+        }
+    }
+}
+
+#[command("describe")]
+#[aliases("edit")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[min_args(1)]
+#[description = "Changes the description and emoji of a tag. Leave them out to clear them."]
+#[example = "splatoon \"Matches, splatfests and news\" 🦑"]
+#[help_available(true)]
+async fn describe_tag(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let tagstr = args.single::<String>()?.to_lowercase();
+    let guild_id = msg.guild_id.unwrap().0;
+    let (description, emoji) = match read_description(ctx, GuildId(guild_id), &mut args) {
+        Ok(read) => read,
+        Err(why) => return send_err(ctx, msg, why).await,
+    };
+    let changed = {
+        let conn = &match db_conn(ctx, msg, "describe a tag").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::describe_tag(conn, guild_id, &tagstr, &description, &emoji).await?
+    };
+    tagpanel::refresh_panels(ctx, msg.guild_id).await;
+    match changed {
+        0 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Tag not found!",
+                format!("{tagstr} wasn't registered for this server."),
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Tag updated",
+                format!(
+                    "+ {tagstr} {emoji}\n{}",
+                    match description.as_str() {
+                        "" => "_No description._".to_owned(),
+                        d => sanitize_txt(d),
+                    }
+                ),
+            )
+            .await
+        }
+    }
+}
+
 #[command("remove")]
 #[only_in("guilds")]
 #[required_permissions("MANAGE_GUILD")]
@@ -122,18 +367,9 @@ async fn remove_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandRes
     let mut ok_list: String = String::with_capacity(args.message().len() + (args.len() * 3));
     let mut err_tags: Vec<String> = Vec::with_capacity(args.len());
     {
-        let botdata = ctx.data.read().await;
-        let conn = match botdata.get::<ZweiDbConn>() {
-            Some(conn) => conn,
-            _ => {
-                log::error!("Failed to acquire database connection object to remove tags!");
-                return send_err_titled(
-                    ctx,
-                    msg,
-                    "Catastrophic failure",
-                    "Could not acquire the database connection object.\nContact support if this keeps happening!"
-                ).await;
-            }
+        let conn = &match db_conn(ctx, msg, "remove tags").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
         for tag in args.iter() {
             let tagstr: String = tag?;
@@ -146,6 +382,7 @@ async fn remove_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandRes
             };
         }
     }
+    tagpanel::refresh_panels(ctx, msg.guild_id).await;
     match ok_list.len() {
         1 => {
             send_ok(
@@ -175,7 +412,7 @@ async fn remove_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandRes
                 "Tag not found!",
                 format!(
                     "{} wasn't registered for this server.",
-                    err_tags.get(0).unwrap()
+                    err_tags.first().unwrap()
                 ),
             )
             .await
@@ -196,37 +433,308 @@ async fn remove_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandRes
     }
 }
 
-#[command("list")]
+#[command("rename")]
 #[only_in("guilds")]
-#[min_args(0)]
-#[max_args(0)]
-#[description = "Lets me know you want to see all tags available in the server."]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(2)]
+#[description = "Gives a tag a new name. Everyone stays subscribed to it."]
+#[example = "splat splatoon"]
 #[help_available(true)]
-async fn list_tags(ctx: &Context, msg: &Message) -> CommandResult {
+async fn rename_tag(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let old = args.single::<String>()?.to_lowercase();
+    let new = args.single::<String>()?.to_lowercase();
     let guild_id = msg.guild_id.unwrap().0;
-    {
-        let botdata = ctx.data.read().await;
-        let conn = match botdata.get::<ZweiDbConn>() {
-            Some(conn) => conn,
-            _ => {
-                log::error!("Failed to acquire database connection object to list tags!");
+    let renamed = {
+        let conn = &match db_conn(ctx, msg, "rename a tag").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        if dbx::get_tag_info(conn, guild_id, &new).await?.is_some() {
+            return send_err_titled(
+                ctx,
+                msg,
+                "Name already taken",
+                format!("{new} is already a tag or an alias in this server."),
+            )
+            .await;
+        }
+        dbx::rename_tag(conn, guild_id, &old, &new).await?
+    };
+    tagpanel::refresh_panels(ctx, msg.guild_id).await;
+    match renamed {
+        0 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Tag not found!",
+                format!("{old} wasn't registered for this server."),
+            )
+            .await
+        }
+        _ => {
+            send_ok(
+                ctx,
+                msg,
+                "Tag renamed",
+                format!("{old} is now called {new}, and everyone stays subscribed."),
+            )
+            .await
+        }
+    }
+}
+
+#[command("alias")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(2)]
+#[description = "Adds another name for a tag. Pinging or subscribing to the alias works the same as using the tag."]
+#[example = "splatoon splat"]
+#[help_available(true)]
+async fn alias_tag(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let tagstr = args.single::<String>()?.to_lowercase();
+    let alias = args.single::<String>()?.to_lowercase();
+    let guild_id = msg.guild_id.unwrap().0;
+    let added = {
+        let conn = &match db_conn(ctx, msg, "alias a tag").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        let tag = match dbx::get_tag_info(conn, guild_id, &tagstr).await? {
+            Some(tag) => tag,
+            None => {
                 return send_err_titled(
                     ctx,
                     msg,
-                    "Catastrophic failure",
-                    "Could not acquire the database connection object.\nContact support if this keeps happening!"
-                ).await;
+                    "Tag not found!",
+                    format!("{tagstr} wasn't registered for this server."),
+                )
+                .await;
             }
         };
-        let tags = dbx::get_server_tags(conn, guild_id).await?;
-        send_ok(
+        dbx::add_alias(conn, guild_id, tag.tagid, &alias)
+            .await
+            .map(|added| (added, tag.tagname))
+    };
+    match added {
+        Ok((1.., tagname)) => {
+            send_ok(
+                ctx,
+                msg,
+                "Alias added",
+                format!("Pinging or subscribing to {alias} now works the same as {tagname}."),
+            )
+            .await
+        }
+        _ => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Name already taken",
+                format!("{alias} is already a tag or an alias in this server."),
+            )
+            .await
+        }
+    }
+}
+
+#[command("unalias")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Removes an alias. The tag it belonged to and its subscribers stay as they are."]
+#[example = "splat"]
+#[help_available(true)]
+async fn unalias_tag(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let alias = args.single::<String>()?.to_lowercase();
+    let guild_id = msg.guild_id.unwrap().0;
+    let removed = {
+        let conn = &match db_conn(ctx, msg, "remove an alias").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::remove_alias(conn, guild_id, &alias).await?
+    };
+    match removed {
+        0 => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Alias not found!",
+                format!("{alias} isn't an alias in this server."),
+            )
+            .await
+        }
+        _ => send_ok(ctx, msg, "Alias removed", format!("{alias} is gone.")).await,
+    }
+}
+
+#[command("merge")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(2)]
+#[description = "Merges the first tag into the second. Its subscribers get subscribed to the second tag, and its name becomes an alias of it."]
+#[example = "splat splatoon"]
+#[help_available(true)]
+async fn merge_tags(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let fromstr = args.single::<String>()?.to_lowercase();
+    let intostr = args.single::<String>()?.to_lowercase();
+    let guild_id = msg.guild_id.unwrap().0;
+    let (from, into, moved) = {
+        let conn = &match db_conn(ctx, msg, "merge tags").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        let from = dbx::get_tag_info(conn, guild_id, &fromstr).await?;
+        let into = dbx::get_tag_info(conn, guild_id, &intostr).await?;
+        let (from, into) = match (from, into) {
+            (Some(from), Some(into)) if from.tagid != into.tagid => (from, into),
+            (Some(_), Some(_)) => {
+                return send_err(
+                    ctx,
+                    msg,
+                    format!("{fromstr} and {intostr} are already the same tag."),
+                )
+                .await;
+            }
+            (None, _) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Tag not found!",
+                    format!("{fromstr} wasn't registered for this server."),
+                )
+                .await;
+            }
+            (_, None) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Tag not found!",
+                    format!("{intostr} wasn't registered for this server."),
+                )
+                .await;
+            }
+        };
+        let moved = dbx::merge_tags(conn, guild_id, &from, &into).await?;
+        (from, into, moved)
+    };
+    tagpanel::refresh_panels(ctx, msg.guild_id).await;
+    send_ok(
+        ctx,
+        msg,
+        "Tags merged",
+        format!(
+            "{} is now an alias of {}. I moved {moved} of its {} subscribers over, the rest already were subscribed.",
+            from.tagname, into.tagname, from.subs
+        ),
+    )
+    .await
+}
+
+#[command("list")]
+#[only_in("guilds")]
+#[min_args(0)]
+#[max_args(0)]
+#[description = "Lets me know you want to see all tags available in the server, along with what they're for."]
+#[help_available(true)]
+async fn list_tags(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let tags = {
+        let conn = &match db_conn(ctx, msg, "list tags").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_tag_infos(conn, guild_id).await?
+    };
+    if tags.is_empty() {
+        return send_err_titled(
             ctx,
             msg,
-            "Tags for this server",
-            format!("+ {}", tags.join("\n+ ")),
+            "No tags",
+            "This server doesn't have any tags yet.",
         )
-        .await
+        .await;
     }
+    let pages: Vec<String> = tags
+        .chunks(TAGS_PER_PAGE)
+        .map(|page| page.iter().map(tag_line).collect::<Vec<_>>().join("\n"))
+        .collect();
+    paginate(ctx, msg, "Tags for this server", &pages).await
+}
+
+#[command("info")]
+#[only_in("guilds")]
+#[num_args(1)]
+#[description = "Tells you what a tag is for, who made it and how many people are subscribed to it."]
+#[example = "splatoon"]
+#[help_available(true)]
+async fn tag_info(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let tagstr = args.single::<String>()?.to_lowercase();
+    let guild_id = msg.guild_id.unwrap().0;
+    let (tag, aliases) = {
+        let conn = &match db_conn(ctx, msg, "show a tag").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        match dbx::get_tag_info(conn, guild_id, &tagstr).await? {
+            Some(tag) => {
+                let aliases = dbx::get_aliases(conn, guild_id, Some(tag.tagid)).await?;
+                (tag, aliases)
+            }
+            None => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Tag not found!",
+                    format!("{tagstr} wasn't registered for this server."),
+                )
+                .await;
+            }
+        }
+    };
+    let title = match tag.emoji.as_str() {
+        "" => tag.tagname.clone(),
+        emoji => format!("{emoji} {}", tag.tagname),
+    };
+    let description = match tag.description.as_str() {
+        "" => "_No description._".to_owned(),
+        d => sanitize_txt(d),
+    };
+    let creator = match tag.creator {
+        0 => "_Unknown_".to_owned(),
+        c => format!("<@{c}>"),
+    };
+    let created = match tag.created {
+        0 => "_Unknown_".to_owned(),
+        c => format!("<t:{c}:D>"),
+    };
+    let pingrole = match tag.pingrole {
+        0 => "Everyone".to_owned(),
+        r => format!("<@&{r}>"),
+    };
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    msg.channel_id
+        .send_message(ctx, |mes| {
+            mes.embed(|e| {
+                e.color(color)
+                    .title(title)
+                    .description(description)
+                    .field("Created by", creator, true)
+                    .field("Created", created, true)
+                    .field("Subscribers", tag.subs.to_string(), true)
+                    .field("Who may ping it", pingrole, true)
+                    .field(
+                        "Aliases",
+                        match aliases.len() {
+                            0 => "_None_".to_owned(),
+                            _ => sanitize_txt(&aliases.join(", ")),
+                        },
+                        false,
+                    )
+            })
+        })
+        .await?;
+    Ok(())
 }
 
 #[command("sub")]
@@ -248,18 +756,9 @@ async fn subscribe(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
     let mut ok_list: String = String::with_capacity(args.message().len() + (args.len() * 3));
     let mut err_list = Vec::with_capacity(args.len());
     {
-        let botdata = ctx.data.read().await;
-        let conn = match botdata.get::<ZweiDbConn>() {
-            Some(conn) => conn,
-            _ => {
-                log::error!("Failed to acquire database connection object to add subscription!");
-                return send_err_titled(
-                    ctx,
-                    msg,
-                    "Catastrophic failure",
-                    "Could not acquire the database connection object.\nContact support if this keeps happening!"
-                ).await;
-            }
+        let conn = &match db_conn(ctx, msg, "add subscription").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
         for tag in args.iter() {
             let tagstr: String = tag?;
@@ -273,20 +772,15 @@ async fn subscribe(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
             };
         }
     }
-    match ok_list.len() {
-        1.. => {
-            send_ok(
-                ctx,
-                msg,
-                "Subscribed successfully!",
-                format!("You are now subscribed to:{ok_list}"),
-            )
-            .await?;
-        }
-        _ => {
-            ();
-        }
-    };
+    if !ok_list.is_empty() {
+        send_ok(
+            ctx,
+            msg,
+            "Subscribed successfully!",
+            format!("You are now subscribed to:{ok_list}"),
+        )
+        .await?;
+    }
     match err_list.len() {
         1.. => {
             send_err_titled(
@@ -323,18 +817,9 @@ async fn unsubscribe(ctx: &Context, msg: &Message, mut args: Args) -> CommandRes
     let mut ok_list: String = String::with_capacity(args.message().len() + (args.len() * 3));
     let mut err_list = Vec::with_capacity(args.len());
     {
-        let botdata = ctx.data.read().await;
-        let conn = match botdata.get::<ZweiDbConn>() {
-            Some(conn) => conn,
-            _ => {
-                log::error!("Failed to acquire database connection object to unsubscribe user!");
-                return send_err_titled(
-                    ctx,
-                    msg,
-                    "Catastrophic failure",
-                    "Could not acquire the database connection object.\nContact support if this keeps happening!"
-                ).await;
-            }
+        let conn = &match db_conn(ctx, msg, "unsubscribe user").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
         for tag in args.iter() {
             let tagstr: String = tag?;
@@ -348,20 +833,15 @@ async fn unsubscribe(ctx: &Context, msg: &Message, mut args: Args) -> CommandRes
             };
         }
     }
-    match ok_list.len() {
-        1.. => {
-            send_ok(
-                ctx,
-                msg,
-                "Unsubscribed successfully!",
-                format!("You are no longer subscribed to:{ok_list}"),
-            )
-            .await?;
-        }
-        _ => {
-            ();
-        }
-    };
+    if !ok_list.is_empty() {
+        send_ok(
+            ctx,
+            msg,
+            "Unsubscribed successfully!",
+            format!("You are no longer subscribed to:{ok_list}"),
+        )
+        .await?;
+    }
     match err_list.len() {
         1.. => {
             send_err_titled(
@@ -386,30 +866,29 @@ async fn list_subs(ctx: &Context, msg: &Message) -> CommandResult {
     let guild_id = msg.guild_id.unwrap().0;
     let tags;
     {
-        let botdata = ctx.data.read().await;
-        let conn = match botdata.get::<ZweiDbConn>() {
-            Some(conn) => conn,
-            _ => {
-                log::error!("Failed to acquire database connection object to list subscriptions!");
-                return send_err_titled(
-                    ctx,
-                    msg,
-                    "Catastrophic failure",
-                    "Could not acquire the database connection object.\nContact support if this keeps happening!"
-                ).await;
-            }
+        let conn = &match db_conn(ctx, msg, "list subscriptions").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
-        tags = dbx::usersubs(conn, guild_id, uid).await?;
+        tags = dbx::user_subscriptions(conn, guild_id, uid).await?;
     }
     match tags.len() {
         1.. => {
+            let lines: Vec<String> = tags
+                .iter()
+                .map(|sub| match sub.delivery.as_str() {
+                    "dm" => format!("+ {} (by DM)", sub.tagname),
+                    "both" => format!("+ {} (mention and DM)", sub.tagname),
+                    _ => format!("+ {}", sub.tagname),
+                })
+                .collect();
             send_ok(
                 ctx,
                 msg,
                 "Your subcriptions",
                 format!(
-                    "For this server, you are currently subscribed to:\n+ {}",
-                    tags.join("\n+ ")
+                    "For this server, you are currently subscribed to:\n{}",
+                    lines.join("\n")
                 ),
             )
             .await
@@ -427,6 +906,462 @@ async fn list_subs(ctx: &Context, msg: &Message) -> CommandResult {
     }
 }
 
+#[command("delivery")]
+#[only_in("guilds")]
+#[aliases("notify")]
+#[min_args(1)]
+#[description = "Picks how you hear about tags being pinged: `channel` for a mention, `dm` for a DM with a link to the ping, or `both`. Applies to all your subscriptions, or only to the tags given."]
+#[example = "dm"]
+#[example = "both splatoon minecraft"]
+#[help_available(true)]
+async fn set_delivery(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let delivery = args.single::<String>()?.to_lowercase();
+    if !dbx::DELIVERIES.contains(&delivery.as_str()) {
+        return send_err(ctx, msg, "Please pick `channel`, `dm` or `both`.").await;
+    }
+    let guild_id = msg.guild_id.unwrap().0;
+    let auth = msg.author.id.0;
+    let tags: Vec<String> = args
+        .iter::<String>()
+        .filter_map(Result::ok)
+        .map(|t| t.to_lowercase())
+        .collect();
+    let mut changed: u64 = 0;
+    let mut err_list = Vec::new();
+    {
+        let conn = &match db_conn(ctx, msg, "set delivery").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        if tags.is_empty() {
+            changed = dbx::set_delivery(conn, guild_id, None, auth, &delivery).await?;
+        }
+        for tag in &tags {
+            match dbx::set_delivery(conn, guild_id, Some(tag), auth, &delivery).await {
+                Ok(1..) => changed += 1,
+                _ => err_list.push(tag.clone()),
+            }
+        }
+    }
+    let how = match delivery.as_str() {
+        "dm" => "by DM",
+        "both" => "with a mention and by DM",
+        _ => "with a mention",
+    };
+    if changed > 0 {
+        send_ok(
+            ctx,
+            msg,
+            "Delivery updated",
+            format!("You'll hear about pings of {changed} of your subscriptions {how}."),
+        )
+        .await?;
+    }
+    match (err_list.len(), changed) {
+        (1.., _) => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Delivery not changed",
+                format!(
+                    "You aren't subscribed to these tags, or they could not be found in this server:\n+ {}",
+                    err_list.join("\n+ ")
+                ),
+            )
+            .await
+        }
+        (_, 0) => {
+            send_err(
+                ctx,
+                msg,
+                "You are currently not subscribed to any tags in this server.",
+            )
+            .await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// # ping_refusals
+/// Checks a member's ping against the tags' required roles and the guild's
+/// cooldowns and daily quota. Returns why the ping isn't allowed, or nothing
+/// if it is.
+async fn ping_refusals(
+    conn: &Pool,
+    guild: u64,
+    member: &Member,
+    tags: &[dbx::TagInfo],
+) -> Result<Vec<String>, SQLerr> {
+    let now = Utc::now().timestamp();
+    let limits = dbx::get_tag_limits(conn, guild).await?;
+    let mut refusals = Vec::new();
+    for tag in tags {
+        if tag.pingrole != 0 && !member.roles.contains(&RoleId(tag.pingrole as u64)) {
+            refusals.push(format!(
+                "Only members with <@&{}> may ping {}.",
+                tag.pingrole, tag.tagname
+            ));
+        }
+        if limits.tag_cooldown > 0 {
+            let wait = dbx::last_tag_ping(conn, tag.tagid)
+                .await?
+                .saturating_add(limits.tag_cooldown)
+                .saturating_sub(now);
+            if wait > 0 {
+                refusals.push(format!(
+                    "{} can be pinged again in {}.",
+                    tag.tagname,
+                    fmt_duration(wait as u64)
+                ));
+            }
+        }
+    }
+    let (last, today) = dbx::user_pings_since(conn, guild, member.user.id.0, now - 86400).await?;
+    if limits.user_cooldown > 0 {
+        let wait = last
+            .saturating_add(limits.user_cooldown)
+            .saturating_sub(now);
+        if wait > 0 {
+            refusals.push(format!(
+                "You can ping again in {}.",
+                fmt_duration(wait as u64)
+            ));
+        }
+    }
+    if limits.daily_quota > 0 && today + tags.len() as i64 > limits.daily_quota {
+        refusals.push(format!(
+            "You may ping {} tags a day, and already pinged {today}.",
+            limits.daily_quota
+        ));
+    }
+    Ok(refusals)
+}
+
+/// # describe_limits
+/// Describes the limits on pinging tags, one limit per line.
+fn describe_limits(limits: &dbx::TagLimits) -> String {
+    let secs = |secs: i64| match secs {
+        0 => "off".to_owned(),
+        s => fmt_duration(s as u64),
+    };
+    let quota = match limits.daily_quota {
+        0 => "off".to_owned(),
+        q => format!("{q} tags"),
+    };
+    format!(
+        "Cooldown per tag: {}\nCooldown per member: {}\nDaily quota per member: {quota}\n\nModerators aren't held to these.",
+        secs(limits.tag_cooldown),
+        secs(limits.user_cooldown)
+    )
+}
+
+/// # update_limits
+/// Changes this guild's limits on pinging tags and shows the result. Shared
+/// by all commands that change a limit.
+async fn update_limits<F>(ctx: &Context, msg: &Message, change: F) -> CommandResult
+where
+    F: FnOnce(&mut dbx::TagLimits),
+{
+    let guild_id = msg.guild_id.unwrap().0;
+    let limits = {
+        let conn = &match db_conn(ctx, msg, "change tag limits").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        let mut limits = dbx::get_tag_limits(conn, guild_id).await?;
+        change(&mut limits);
+        dbx::set_tag_limits(conn, guild_id, &limits).await?;
+        limits
+    };
+    send_ok(ctx, msg, "Tag limits updated", describe_limits(&limits)).await
+}
+
+/// # MAX_COOLDOWN
+/// The longest cooldown that can be set on pinging tags, in seconds.
+const MAX_COOLDOWN: u64 = 30 * 86400;
+
+/// # parse_cooldown
+/// Reads a cooldown as a duration of up to `MAX_COOLDOWN`, or `off` for none.
+fn parse_cooldown(args: &Args) -> Option<i64> {
+    match args.current()? {
+        off if off.eq_ignore_ascii_case("off") || off.eq_ignore_ascii_case("none") => Some(0),
+        txt => parse_duration(txt)
+            .filter(|secs| *secs <= MAX_COOLDOWN)
+            .map(|secs| secs as i64),
+    }
+}
+
+#[command("limits")]
+#[only_in("guilds")]
+#[max_args(0)]
+#[description = "Shows how often tags may be pinged in this server."]
+#[help_available(true)]
+async fn tag_limits(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let limits = {
+        let conn = &match db_conn(ctx, msg, "show tag limits").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_tag_limits(conn, guild_id).await?
+    };
+    send_ok(ctx, msg, "Tag limits", describe_limits(&limits)).await
+}
+
+#[command("cooldown")]
+#[aliases("tagcooldown")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Sets how long the same tag can't be pinged again after it was used. Use `off` to turn it off."]
+#[example = "30m"]
+#[help_available(true)]
+async fn tag_cooldown(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    match parse_cooldown(&args) {
+        Some(secs) => update_limits(ctx, msg, |limits| limits.tag_cooldown = secs).await,
+        None => {
+            send_err(
+                ctx,
+                msg,
+                "Please give me a duration of up to 30 days like `30m`, or `off`.",
+            )
+            .await
+        }
+    }
+}
+
+#[command("usercooldown")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Sets how long a member has to wait between pinging tags. Use `off` to turn it off."]
+#[example = "5m"]
+#[help_available(true)]
+async fn user_cooldown(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    match parse_cooldown(&args) {
+        Some(secs) => update_limits(ctx, msg, |limits| limits.user_cooldown = secs).await,
+        None => {
+            send_err(
+                ctx,
+                msg,
+                "Please give me a duration of up to 30 days like `5m`, or `off`.",
+            )
+            .await
+        }
+    }
+}
+
+#[command("quota")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(1)]
+#[description = "Sets how many tags a member may ping per day. Use `off` to turn it off."]
+#[example = "10"]
+#[help_available(true)]
+async fn daily_quota(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let quota = match args.current() {
+        Some(off) if off.eq_ignore_ascii_case("off") => 0,
+        _ => match args.parse::<u32>() {
+            Ok(quota) => quota as i64,
+            Err(_) => {
+                return send_err(ctx, msg, "Please give me an amount of tags, or `off`.").await;
+            }
+        },
+    };
+    update_limits(ctx, msg, |limits| limits.daily_quota = quota).await
+}
+
+#[command("role")]
+#[aliases("pingrole")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(2)]
+#[description = "Only lets members with a role ping a tag. Use `none` to let everyone ping it again."]
+#[example = "splatoon @Event Hosts"]
+#[example = "splatoon none"]
+#[help_available(true)]
+async fn tag_role(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let tagstr = args.single::<String>()?.to_lowercase();
+    args.trimmed();
+    let role = match args.current() {
+        Some(none) if none.eq_ignore_ascii_case("none") => 0,
+        _ => match args.parse::<RoleId>() {
+            Ok(role) => role.0,
+            Err(_) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Invalid role!",
+                    "Please give me a role mention or an ID, or `none`.",
+                )
+                .await;
+            }
+        },
+    };
+    let guild_id = msg.guild_id.unwrap().0;
+    let changed = {
+        let conn = &match db_conn(ctx, msg, "set a tag's role").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::set_ping_role(conn, guild_id, &tagstr, role).await?
+    };
+    match (changed, role) {
+        (0, _) => {
+            send_err_titled(
+                ctx,
+                msg,
+                "Tag not found!",
+                format!("{tagstr} wasn't registered for this server."),
+            )
+            .await
+        }
+        (_, 0) => {
+            send_ok(
+                ctx,
+                msg,
+                "Tag role removed",
+                format!("Everyone may ping {tagstr} again."),
+            )
+            .await
+        }
+        (_, r) => {
+            send_ok(
+                ctx,
+                msg,
+                "Tag role set",
+                format!("Only members with <@&{r}> may ping {tagstr} now."),
+            )
+            .await
+        }
+    }
+}
+
+#[command("log")]
+#[aliases("pings", "history")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_MESSAGES")]
+#[max_args(1)]
+#[description = "Shows who pinged tags recently, and where. Give a tag to only see its pings."]
+#[example = "splatoon"]
+#[help_available(true)]
+async fn ping_log(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let tag = args.single::<String>().ok().map(|t| t.to_lowercase());
+    let guild_id = msg.guild_id.unwrap().0;
+    let pings = {
+        let conn = &match db_conn(ctx, msg, "show tag pings").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
+        };
+        dbx::get_tag_pings(conn, guild_id, tag.as_ref(), 50).await?
+    };
+    if pings.is_empty() {
+        return send_ok(ctx, msg, "Tag pings", "Nobody pinged any tags yet.").await;
+    }
+    // Oldest first, so fit_embed drops the oldest ones if they don't fit.
+    let lines: Vec<String> = pings
+        .iter()
+        .rev()
+        .map(|p| {
+            format!(
+                "<t:{}:f> <@{}> pinged **{}** in <#{}>",
+                p.pinged,
+                p.userid,
+                sanitize_txt(&p.tagname),
+                p.channelid
+            )
+        })
+        .collect();
+    send_ok(ctx, msg, "Tag pings", fit_embed(&lines)).await
+}
+
+#[command("panel")]
+#[only_in("guilds")]
+#[required_permissions("MANAGE_GUILD")]
+#[max_args(1)]
+#[description = "Posts a menu members can subscribe and unsubscribe with, here or in the given channel. It stays up to date as tags change."]
+#[example = "#roles"]
+#[help_available(true)]
+async fn tag_panel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    args.trimmed();
+    let channel = match args.current() {
+        None => msg.channel_id,
+        Some(_) => match args.parse::<ChannelId>() {
+            Ok(c) => c,
+            Err(_) => {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Invalid channel!",
+                    "Please give me a channel mention or an ID.",
+                )
+                .await;
+            }
+        },
+    };
+    if let Err(e) = tagpanel::post_panel(ctx, msg.guild_id.unwrap(), channel).await {
+        log::warn!("Couldn't post a tag panel in {}\n\t{e}", channel.0);
+        return send_err(
+            ctx,
+            msg,
+            format!("I couldn't post the panel in <#{}>.", channel.0),
+        )
+        .await;
+    }
+    if channel != msg.channel_id {
+        send_ok(
+            ctx,
+            msg,
+            "Tag panel posted",
+            format!("Members can pick their tags in <#{}> now.", channel.0),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// # MAX_CONCURRENT_DMS
+/// How many subscribers `tag ping` DMs at the same time.
+const MAX_CONCURRENT_DMS: usize = 5;
+
+/// # dm_subbers
+/// DMs subscribers who asked to hear about pings that way, with a link to the
+/// ping. Returns the users that couldn't be reached.
+async fn dm_subbers(ctx: &Context, msg: &Message, tagmsg: &str, users: HashSet<u64>) -> Vec<u64> {
+    let guildname = msg
+        .guild_id
+        .and_then(|g| g.name(ctx))
+        .unwrap_or_else(|| String::from("Unknown Server"));
+    let text = format!(
+        "{} pinged {} in <#{}> on {guildname}.\n[Jump to the message]({})",
+        msg.author.tag(),
+        tagmsg,
+        msg.channel_id.0,
+        msg.link()
+    );
+    stream::iter(users)
+        .map(|user| {
+            let text = &text;
+            async move {
+                let sent = try_dm(ctx, UserId(user), "Tag pinged", text).await;
+                (user, sent)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_DMS)
+        .filter_map(|(user, sent)| async move {
+            match sent {
+                Ok(_) => None,
+                Err(e) => {
+                    log::debug!("Couldn't DM {user} about a tag ping\n\t{e}");
+                    Some(user)
+                }
+            }
+        })
+        .collect()
+        .await
+}
+
 #[command("ping")]
 #[only_in("guilds")]
 #[description = "I'll tell everyone who wants to know that this tag was used."]
@@ -452,28 +1387,36 @@ async fn ping_all_subbers(ctx: &Context, msg: &Message, args: Args) -> CommandRe
         .message()
         .replace(", ", " ")
         .split(" ")
-        .map(|a| String::from(a))
+        .map(String::from)
         .collect::<HashSet<String>>()
         .iter()
         .map(|s| s.to_owned())
         .collect();
     let guild_id = msg.guild_id.unwrap().0;
+    // Moderators aren't held to the limits on pinging.
+    let member = match msg.member(ctx).await {
+        Ok(member) if member.permissions(ctx).is_ok_and(|p| p.manage_messages()) => None,
+        Ok(member) => Some(member),
+        Err(e) => {
+            log::warn!("Couldn't look up {} to ping tags\n\t{e}", msg.author.id.0);
+            return send_err(
+                ctx,
+                msg,
+                "I couldn't check whether you may ping these tags.",
+            )
+            .await;
+        }
+    };
+    let mut pinged: Vec<dbx::TagInfo> = Vec::with_capacity(tags.len());
     let mut users: HashSet<u64> = HashSet::new();
+    let mut dm_users: HashSet<u64> = HashSet::new();
+    let mut deliveries: HashMap<u64, String> = HashMap::new();
     let mut failed: Vec<String> = Vec::new();
     let mut tagmsg = String::new();
     {
-        let botdata = ctx.data.read().await;
-        let conn = match botdata.get::<ZweiDbConn>() {
-            Some(conn) => conn,
-            _ => {
-                log::error!("Failed to acquire database connection object to tag users!");
-                return send_err_titled(
-                    ctx,
-                    msg,
-                    "Catastrophic failure",
-                    "Could not acquire the database connection object.\nContact support if this keeps happening!"
-                ).await;
-            }
+        let conn = &match db_conn(ctx, msg, "tag users").await {
+            Ok(conn) => conn,
+            Err(res) => return res,
         };
         let tagcount = dbx::are_tags_in_server(conn, guild_id, &tags);
         let valid = match tagcount.await {
@@ -496,20 +1439,54 @@ async fn ping_all_subbers(ctx: &Context, msg: &Message, args: Args) -> CommandRe
             ).await;
         }
         for tag in &tags {
-            if tag == "" || tag == " " {
+            // Aliases of the same tag only count once.
+            if let Some(info) = dbx::get_tag_info(conn, guild_id, tag).await? {
+                if !pinged.iter().any(|p| p.tagid == info.tagid) {
+                    pinged.push(info);
+                }
+            }
+        }
+        if let Some(member) = &member {
+            let refusals = ping_refusals(conn, guild_id, member, &pinged).await?;
+            if !refusals.is_empty() {
+                return send_err_titled(
+                    ctx,
+                    msg,
+                    "Can't ping that right now",
+                    format!("+ {}", refusals.join("\n+ ")),
+                )
+                .await;
+            }
+        }
+        for tag in &tags {
+            if tag.is_empty() || tag == " " {
                 continue;
             }
-            let subs = dbx::get_subbers(conn, guild_id, &tag);
+            let subs = dbx::get_subbers(conn, guild_id, tag);
             match subs.await {
                 Ok(s) => {
-                    users.extend(s);
+                    for sub in s {
+                        let merged = match deliveries.get(&(sub.userid as u64)) {
+                            Some(prev) => merge_delivery(prev, &sub.delivery),
+                            None => sub.delivery,
+                        };
+                        deliveries.insert(sub.userid as u64, merged);
+                    }
                 }
                 Err(_) => failed.push(tag.clone()),
             };
         }
         tagmsg.push_str(tags.join(", ").as_str());
     }
-    if failed.len() > 0 {
+    for (user, delivery) in deliveries {
+        if delivery != "dm" {
+            users.insert(user);
+        }
+        if delivery != "channel" {
+            dm_users.insert(user);
+        }
+    }
+    if !failed.is_empty() {
         log::warn!(
             "Failed to find tags for {guild_id}: `{}`",
             failed.join(", ")
@@ -525,7 +1502,7 @@ async fn ping_all_subbers(ctx: &Context, msg: &Message, args: Args) -> CommandRe
         )
         .await?;
     }
-    if users.len() == 0 {
+    if users.is_empty() && dm_users.is_empty() {
         log::warn!(
             "Failed to find sunscribed users for {guild_id}: `{}`",
             failed.join(", ")
@@ -538,6 +1515,16 @@ async fn ping_all_subbers(ctx: &Context, msg: &Message, args: Args) -> CommandRe
         )
         .await;
     } else {
+        {
+            let botdata = ctx.data.read().await;
+            if let Some(conn) = botdata.get::<ZweiDbConn>() {
+                for tag in &pinged {
+                    let _ =
+                        dbx::log_tag_ping(conn, guild_id, tag, msg.author.id.0, msg.channel_id.0)
+                            .await;
+                }
+            }
+        }
         let mut paginated: Vec<String> = Vec::new();
         let initial = tagmsg.chars().count();
         let mut charcount: usize = initial;
@@ -559,12 +1546,27 @@ async fn ping_all_subbers(ctx: &Context, msg: &Message, args: Args) -> CommandRe
             pingmsg.push_str(format!(" <@{user}>").as_str());
             charcount += 22;
         }
-        paginated.push(pingmsg);
+        // Nothing to mention if everyone asked for a DM instead.
+        if pingmsg.len() > tagmsg.len() {
+            paginated.push(pingmsg);
+        }
         for page in paginated {
             msg.channel_id
                 .send_message(ctx, |mes| mes.content(page))
                 .await?;
         }
+        let undelivered = dm_subbers(ctx, msg, &tagmsg, dm_users).await;
+        if !undelivered.is_empty() {
+            let lines: Vec<String> = undelivered.iter().map(|u| format!("+ <@{u}>")).collect();
+            let intro = "I couldn't DM these subscribers, they might not accept DMs from me:\n";
+            send_err_titled(
+                ctx,
+                msg,
+                "Some DMs failed",
+                format!("{intro}{}", fit_lines(&lines, 4000 - intro.len())),
+            )
+            .await?;
+        }
     }
     Ok(())
 }
@@ -572,11 +1574,25 @@ async fn ping_all_subbers(ctx: &Context, msg: &Message, args: Args) -> CommandRe
 #[group("Tag")]
 #[commands(
     add_tags,
+    describe_tag,
     remove_tags,
+    rename_tag,
+    alias_tag,
+    unalias_tag,
+    merge_tags,
     list_tags,
+    tag_info,
     subscribe,
     unsubscribe,
     list_subs,
+    set_delivery,
+    tag_limits,
+    tag_cooldown,
+    user_cooldown,
+    daily_quota,
+    tag_role,
+    ping_log,
+    tag_panel,
     ping_all_subbers
 )]
 #[summary = "Tag subscription for easily pinging the people interested in certain subjects. Tags are case-insensitive. Provide only tags to ping subscribed users."]
@@ -584,3 +1600,57 @@ async fn ping_all_subbers(ctx: &Context, msg: &Message, args: Args) -> CommandRe
 #[help_available(true)]
 #[default_command(ping_all_subbers)]
 struct Tag;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emoji_unicode() {
+        assert!(is_emoji("😀"));
+        assert!(is_emoji("⭐"));
+        assert!(is_emoji("❤\u{FE0F}"));
+        assert!(is_emoji("👍🏽"));
+        assert!(is_emoji("👨\u{200D}👩\u{200D}👧"));
+        assert!(is_emoji("🇳🇱"));
+        assert!(is_emoji("1\u{FE0F}\u{20E3}"));
+        assert!(is_emoji("#\u{20E3}"));
+    }
+
+    #[test]
+    fn emoji_custom() {
+        assert!(is_emoji("<:zwei:844167326243880960>"));
+        assert!(is_emoji("<a:zwei:844167326243880960>"));
+        assert!(!is_emoji("<:zwei:>"));
+        assert!(!is_emoji("<zwei>"));
+    }
+
+    #[test]
+    fn emoji_rejects_text() {
+        assert!(!is_emoji(""));
+        assert!(!is_emoji(" "));
+        assert!(!is_emoji("a"));
+        assert!(!is_emoji("1"));
+        assert!(!is_emoji("->"));
+        assert!(!is_emoji("é"));
+        assert!(!is_emoji("😀 hi"));
+        assert!(!is_emoji("\u{FE0F}"));
+        assert!(!is_emoji("\u{200D}😀"));
+        assert!(!is_emoji("11\u{20E3}"));
+    }
+
+    #[test]
+    fn emoji_length_limit() {
+        assert!(is_emoji(&"😀".repeat(16)));
+        assert!(!is_emoji(&"😀".repeat(17)));
+    }
+
+    #[test]
+    fn delivery_merges_to_one_notification() {
+        assert_eq!(merge_delivery("channel", "channel"), "channel");
+        assert_eq!(merge_delivery("dm", "dm"), "dm");
+        assert_eq!(merge_delivery("dm", "channel"), "channel");
+        assert_eq!(merge_delivery("channel", "both"), "both");
+        assert_eq!(merge_delivery("both", "dm"), "both");
+    }
+}
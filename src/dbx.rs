@@ -1,3 +1,4 @@
+use chrono::Utc;
 use log::{error, trace};
 use serenity::prelude::TypeMapKey;
 pub use sqlx::{query, query_as, Error as SQLerr, SqlitePool as Pool};
@@ -102,23 +103,59 @@ pub async fn get_server_tags(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<String>>
         .map(|r| r.iter().map(|row| row.tagname.to_owned()).collect())
 }
 
+/// # DELIVERIES
+/// The ways subscribers can hear about a tag being pinged: a mention in the
+/// channel, a DM with a link to the ping, or both.
+pub const DELIVERIES: [&str; 3] = ["channel", "dm", "both"];
+
+/// # Subscription
+/// A subscription to a tag and how its subscriber wants to hear about pings,
+/// which is one of [`DELIVERIES`].
+pub struct Subscription {
+    pub tagname: String,
+    pub userid: i64,
+    pub delivery: String,
+}
+
 /// # get_subbers
-/// Get all users subscribed to a tag in this guild. Returned vec might be empty.
-pub async fn get_subbers(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<Vec<u64>> {
+/// Get all subscriptions to a tag in this guild. Returned vec might be empty.
+/// The tag may also be one of its aliases.
+pub async fn get_subbers(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<Vec<Subscription>> {
     let g = guild as i64;
-    query!("SELECT userid FROM tagsubs WHERE tagid = (SELECT tagid FROM servertags WHERE serverid = ? AND tagname = ?)", g, tag)
+    query_as!(
+        Subscription,
+        "SELECT servertags.tagname, tagsubs.userid, tagsubs.delivery FROM tagsubs JOIN servertags ON servertags.tagid = tagsubs.tagid WHERE tagsubs.tagid = (SELECT tagid FROM servertags WHERE serverid = ? AND tagname = ? UNION ALL SELECT tagid FROM tagaliases WHERE serverid = ? AND alias = ?)",
+        g,
+        tag,
+        g,
+        tag
+    )
     .fetch_all(conn)
     .await
-    .map(|ids|ids.iter().map(|id| id.userid as u64).collect())
 }
 
 /// # add_tag
-/// Register a tag for use in this guild.
-pub async fn add_tag(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<u64> {
+/// Register a tag for use in this guild, along with a description of what it's
+/// for, who added it and an emoji to show next to it. The description and
+/// emoji may be empty. Returns 0 if the name is already used as an alias.
+pub async fn add_tag(
+    conn: &Pool,
+    guild: u64,
+    tag: &String,
+    description: &str,
+    creator: u64,
+    emoji: &str,
+) -> ZweiDbRes<u64> {
     let g = guild as i64;
+    let c = creator as i64;
     rowcount!(
         query!(
-            "INSERT INTO servertags (serverid, tagname) VALUES (?, ?)",
+            "INSERT INTO servertags (serverid, tagname, description, creator, created, emoji) SELECT ?, ?, ?, ?, strftime('%s', 'now'), ? WHERE NOT EXISTS (SELECT 1 FROM tagaliases WHERE serverid = ? AND alias = ?)",
+            g,
+            tag,
+            description,
+            c,
+            emoji,
             g,
             tag
         )
@@ -130,6 +167,78 @@ pub async fn add_tag(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<u64> {
     )
 }
 
+/// # describe_tag
+/// Changes the description and emoji of a tag registered to this guild.
+/// Returns 0 if there is no such tag.
+pub async fn describe_tag(
+    conn: &Pool,
+    guild: u64,
+    tag: &String,
+    description: &str,
+    emoji: &str,
+) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "UPDATE servertags SET description = ?, emoji = ? WHERE serverid = ? AND tagname = ?",
+            description,
+            emoji,
+            g,
+            tag
+        )
+        .execute(conn),
+        "Describing tag {} for guild ID {}",
+        "Failed to describe tag {} for guild ID {}",
+        tag,
+        guild
+    )
+}
+
+/// # TagInfo
+/// A tag with everything known about it, including how many users are
+/// subscribed to it. `pingrole` is the role needed to ping it, or 0.
+pub struct TagInfo {
+    pub tagid: i64,
+    pub tagname: String,
+    pub description: String,
+    pub creator: i64,
+    pub created: i64,
+    pub emoji: String,
+    pub pingrole: i64,
+    pub subs: i64,
+}
+
+/// # get_tag_info
+/// Fetches a tag registered to this guild with all its details. The tag may
+/// also be one of its aliases, which gets the tag it belongs to.
+pub async fn get_tag_info(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<Option<TagInfo>> {
+    let g = guild as i64;
+    query_as!(
+        TagInfo,
+        r#"SELECT tagid, tagname, description, creator, created, emoji, pingrole, (SELECT COUNT(*) FROM tagsubs WHERE tagsubs.tagid = servertags.tagid) AS "subs!: i64" FROM servertags WHERE serverid = ? AND (tagname = ? OR tagid = (SELECT tagid FROM tagaliases WHERE serverid = ? AND alias = ?))"#,
+        g,
+        tag,
+        g,
+        tag
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// # get_tag_infos
+/// Fetches all tags registered to this guild with their details, sorted by
+/// name. This Vec might be empty if the result set is.
+pub async fn get_tag_infos(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<TagInfo>> {
+    let g = guild as i64;
+    query_as!(
+        TagInfo,
+        r#"SELECT tagid, tagname, description, creator, created, emoji, pingrole, (SELECT COUNT(*) FROM tagsubs WHERE tagsubs.tagid = servertags.tagid) AS "subs!: i64" FROM servertags WHERE serverid = ? ORDER BY tagname"#,
+        g
+    )
+    .fetch_all(conn)
+    .await
+}
+
 /// # remove_tag
 /// Removes a tag registered to this guild, if present.
 pub async fn remove_tag(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<u64> {
@@ -151,11 +260,13 @@ pub async fn remove_tag(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<u64>
 /// # get_tag_id
 /// Function for internal use to get the ID of a tag registered for the current guild.
 /// This is a helper function to prevent duplicate tags across guilds from becoming
-/// a problem or a source of collisions.
+/// a problem or a source of collisions. Aliases resolve to the tag they belong to.
 async fn get_tag_id(conn: &Pool, guild: u64, tag: &String) -> ZweiDbRes<i64> {
     let g = guild as i64;
     query!(
-        "SELECT tagid FROM servertags WHERE serverid = ? AND tagname = ?",
+        r#"SELECT tagid AS "tagid!: i64" FROM servertags WHERE serverid = ? AND tagname = ? UNION ALL SELECT tagid FROM tagaliases WHERE serverid = ? AND alias = ?"#,
+        g,
+        tag,
         g,
         tag
     )
@@ -172,7 +283,7 @@ pub async fn sub_to(conn: &Pool, guild: u64, tag: &String, uid: u64) -> ZweiDbRe
     let u = uid as i64;
     let t = get_tag_id(conn, guild, tag).await?;
     rowcount!(
-        query!("INSERT INTO tagsubs VALUES (?, ?)", t, u).execute(conn),
+        query!("INSERT INTO tagsubs (tagid, userid) VALUES (?, ?)", t, u).execute(conn),
         "Subscribing user ID {} to tag ID {}",
         "Something went wrong, failed to subscribe user {} to {}",
         u,
@@ -208,33 +319,1872 @@ pub async fn usersubs(conn: &Pool, guild: u64, uid: u64) -> ZweiDbRes<Vec<String
     .map(|res| res.iter().map(|row| row.tagname.to_owned()).collect())
 }
 
+/// # user_subscriptions
+/// Fetches all subscriptions of a user in the current server, sorted by tag.
+pub async fn user_subscriptions(conn: &Pool, guild: u64, uid: u64) -> ZweiDbRes<Vec<Subscription>> {
+    let g = guild as i64;
+    let u = uid as i64;
+    query_as!(
+        Subscription,
+        "SELECT servertags.tagname, tagsubs.userid, tagsubs.delivery FROM tagsubs JOIN servertags ON servertags.tagid = tagsubs.tagid WHERE servertags.serverid = ? AND tagsubs.userid = ? ORDER BY servertags.tagname",
+        g,
+        u
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # set_delivery
+/// Sets how a user wants to hear about pings of a tag they're subscribed to,
+/// or of all their subscriptions in this guild if no tag is given. `delivery`
+/// must be one of [`DELIVERIES`]. Returns how many subscriptions changed.
+pub async fn set_delivery(
+    conn: &Pool,
+    guild: u64,
+    tag: Option<&String>,
+    uid: u64,
+    delivery: &str,
+) -> ZweiDbRes<u64> {
+    let u = uid as i64;
+    let g = guild as i64;
+    let t = match tag {
+        Some(tag) => Some(get_tag_id(conn, guild, tag).await?),
+        None => None,
+    };
+    rowcount!(
+        query!(
+            "UPDATE tagsubs SET delivery = ? WHERE userid = ? AND tagid IN (SELECT tagid FROM servertags WHERE serverid = ? AND (? IS NULL OR tagid = ?))",
+            delivery,
+            u,
+            g,
+            t,
+            t
+        )
+        .execute(conn),
+        "Setting delivery {} for user ID {} in guild ID {}",
+        "Failed to set delivery {} for user ID {} in guild ID {}",
+        delivery,
+        uid,
+        guild
+    )
+}
+
 /// # are_tags_in_server
 /// Helper function that determines how many of the requested tags are registered
-/// for the current guild. Intended to have an early exit method when a provided
-/// list of tags has no matches in this guild.
-pub async fn are_tags_in_server(conn: &Pool, guild: u64, tagvec: &Vec<String>) -> ZweiDbRes<usize> {
-    get_server_tags(conn, guild)
-        .await
-        .map(|res| res.iter().filter(|tag| tagvec.contains(tag)).count())
+/// for the current guild, counting aliases too. Intended to have an early exit
+/// method when a provided list of tags has no matches in this guild.
+pub async fn are_tags_in_server(conn: &Pool, guild: u64, tagvec: &[String]) -> ZweiDbRes<usize> {
+    let mut known = get_server_tags(conn, guild).await?;
+    known.extend(get_aliases(conn, guild, None).await?);
+    Ok(known.iter().filter(|tag| tagvec.contains(tag)).count())
 }
 
-pub async fn add_warning(conn: &Pool, guild: u64, user: u64, msg: String) -> ZweiDbRes<i32> {
+/// # get_aliases
+/// Fetches the aliases registered in this guild, optionally only those of a
+/// single tag. This Vec might be empty if the result set is.
+pub async fn get_aliases(conn: &Pool, guild: u64, tagid: Option<i64>) -> ZweiDbRes<Vec<String>> {
     let g = guild as i64;
-    let u = user as i64;
-    let res = query!(
-        "INSERT INTO warnings (serverid, userid, message) VALUES (?, ?, ?)",
+    query!(
+        "SELECT alias FROM tagaliases WHERE serverid = ? AND (? IS NULL OR tagid = ?) ORDER BY alias",
         g,
-        u,
-        msg
+        tagid,
+        tagid
     )
-    .execute(conn)
+    .fetch_all(conn)
+    .await
+    .map(|r| r.iter().map(|row| row.alias.to_owned()).collect())
+}
+
+/// # add_alias
+/// Registers another name for a tag. Returns 0 if the name is already used
+/// by a tag in this guild, and fails if it's already an alias.
+pub async fn add_alias(conn: &Pool, guild: u64, tagid: i64, alias: &String) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO tagaliases (serverid, alias, tagid) SELECT ?, ?, ? WHERE NOT EXISTS (SELECT 1 FROM servertags WHERE serverid = ? AND tagname = ?)",
+            g,
+            alias,
+            tagid,
+            g,
+            alias
+        )
+        .execute(conn),
+        "Adding alias {} for tag ID {}",
+        "Failed to add alias {} for tag ID {}",
+        alias,
+        tagid
+    )
+}
+
+/// # remove_alias
+/// Removes an alias registered in this guild, if present.
+pub async fn remove_alias(conn: &Pool, guild: u64, alias: &String) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM tagaliases WHERE serverid = ? AND alias = ?",
+            g,
+            alias
+        )
+        .execute(conn),
+        "Removing alias {} for guild ID {}",
+        "Failed to remove alias {} for guild ID {}",
+        alias,
+        guild
+    )
+}
+
+/// # rename_tag
+/// Gives a tag registered to this guild a new name. The tag keeps its ID, so
+/// subscriptions and aliases stay with it. Returns 0 if there is no such tag
+/// or the new name is used as an alias, and fails if it's used by a tag.
+pub async fn rename_tag(conn: &Pool, guild: u64, old: &String, new: &String) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "UPDATE servertags SET tagname = ? WHERE serverid = ? AND tagname = ? AND NOT EXISTS (SELECT 1 FROM tagaliases WHERE serverid = ? AND alias = ?)",
+            new,
+            g,
+            old,
+            g,
+            new
+        )
+        .execute(conn),
+        "Renaming tag {} to {} for guild ID {}",
+        "Failed to rename tag {} to {} for guild ID {}",
+        old,
+        new,
+        guild
+    )
+}
+
+/// # merge_tags
+/// Merges one tag into another. Subscribers of `from` are subscribed to
+/// `into` unless they already were, `from`'s aliases move over, and `from`
+/// itself is removed and kept as an alias of `into`. All of it happens at
+/// once or not at all. Returns how many subscriptions were moved.
+pub async fn merge_tags(conn: &Pool, guild: u64, from: &TagInfo, into: &TagInfo) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let mut tx = conn.begin().await?;
+    let moved = query!(
+        "INSERT OR IGNORE INTO tagsubs (tagid, userid, delivery) SELECT ?, userid, delivery FROM tagsubs WHERE tagid = ?",
+        into.tagid,
+        from.tagid
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    query!(
+        "UPDATE tagaliases SET tagid = ? WHERE tagid = ?",
+        into.tagid,
+        from.tagid
+    )
+    .execute(&mut *tx)
     .await?;
-    Ok(query!(
-        "SELECT COUNT(warnid) AS warncount FROM warnings WHERE serverid = ? AND userid = ?",
+    query!("DELETE FROM servertags WHERE tagid = ?", from.tagid)
+        .execute(&mut *tx)
+        .await?;
+    query!(
+        "INSERT INTO tagaliases (serverid, alias, tagid) VALUES (?, ?, ?)",
+        g,
+        from.tagname,
+        into.tagid
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await.map_err(|e| {
+        error!(
+            "Failed to merge tag {} into {} for guild ID {guild}\n\t{e}",
+            from.tagname, into.tagname
+        );
+        e
+    })?;
+    trace!(
+        "Merged tag {} into {} for guild ID {guild}, moving {moved} subscriptions",
+        from.tagname,
+        into.tagname
+    );
+    Ok(moved)
+}
+
+/// # set_ping_role
+/// Sets the role members need to ping a tag registered to this guild. Use 0
+/// to let everyone ping it. Returns 0 if there is no such tag.
+pub async fn set_ping_role(conn: &Pool, guild: u64, tag: &String, role: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let r = role as i64;
+    rowcount!(
+        query!(
+            "UPDATE servertags SET pingrole = ? WHERE serverid = ? AND tagname = ?",
+            r,
+            g,
+            tag
+        )
+        .execute(conn),
+        "Setting ping role {} for tag {} in guild ID {}",
+        "Failed to set ping role {} for tag {} in guild ID {}",
+        role,
+        tag,
+        guild
+    )
+}
+
+/// # TagPanel
+/// A message with a menu to subscribe to this guild's tags with.
+pub struct TagPanel {
+    pub messageid: i64,
+    pub serverid: i64,
+    pub channelid: i64,
+}
+
+/// # add_tag_panel
+/// Remembers a message with a menu to subscribe to tags with.
+pub async fn add_tag_panel(conn: &Pool, guild: u64, channel: u64, message: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let c = channel as i64;
+    let m = message as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO tagpanels (messageid, serverid, channelid) VALUES (?, ?, ?)",
+            m,
+            g,
+            c
+        )
+        .execute(conn),
+        "Adding tag panel {} for guild ID {}",
+        "Failed to add tag panel {} for guild ID {}",
+        message,
+        guild
+    )
+}
+
+/// # remove_tag_panel
+/// Forgets a tag panel, for when its message is gone.
+pub async fn remove_tag_panel(conn: &Pool, message: u64) -> ZweiDbRes<u64> {
+    let m = message as i64;
+    rowcount!(
+        query!("DELETE FROM tagpanels WHERE messageid = ?", m).execute(conn),
+        "Removing tag panel {}",
+        "Failed to remove tag panel {}",
+        message
+    )
+}
+
+/// # get_tag_panels
+/// Fetches the tag panels of a guild, or of all guilds if none is given.
+pub async fn get_tag_panels(conn: &Pool, guild: Option<u64>) -> ZweiDbRes<Vec<TagPanel>> {
+    let g = guild.map(|g| g as i64);
+    query_as!(
+        TagPanel,
+        "SELECT messageid, serverid, channelid FROM tagpanels WHERE ? IS NULL OR serverid = ?",
+        g,
+        g
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # TagLimits
+/// How often tags may be pinged in a guild. `tag_cooldown` is the time in
+/// seconds before the same tag can be pinged again, `user_cooldown` the time
+/// before the same member can ping any tag again, and `daily_quota` the
+/// amount of tags a member may ping per day. 0 turns a limit off.
+#[derive(Clone, Default)]
+pub struct TagLimits {
+    pub tag_cooldown: i64,
+    pub user_cooldown: i64,
+    pub daily_quota: i64,
+}
+
+/// # get_tag_limits
+/// Fetches the limits on pinging tags in this guild, defaulting to none.
+pub async fn get_tag_limits(conn: &Pool, guild: u64) -> ZweiDbRes<TagLimits> {
+    let g = guild as i64;
+    query_as!(
+        TagLimits,
+        "SELECT tag_cooldown, user_cooldown, daily_quota FROM tagconf WHERE serverid = ?",
+        g
+    )
+    .fetch_optional(conn)
+    .await
+    .map(Option::unwrap_or_default)
+}
+
+/// # set_tag_limits
+/// Stores all limits on pinging tags for this guild at once.
+pub async fn set_tag_limits(conn: &Pool, guild: u64, limits: &TagLimits) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO tagconf (serverid, tag_cooldown, user_cooldown, daily_quota) VALUES (?, ?, ?, ?) ON CONFLICT(serverid) DO UPDATE SET tag_cooldown = excluded.tag_cooldown, user_cooldown = excluded.user_cooldown, daily_quota = excluded.daily_quota",
+            g,
+            limits.tag_cooldown,
+            limits.user_cooldown,
+            limits.daily_quota
+        )
+        .execute(conn),
+        "Storing tag limits for guild ID {}",
+        "Failed to store tag limits for guild ID {}",
+        guild
+    )
+}
+
+/// # log_tag_ping
+/// Records that a member pinged a tag in a channel.
+pub async fn log_tag_ping(
+    conn: &Pool,
+    guild: u64,
+    tag: &TagInfo,
+    user: u64,
+    channel: u64,
+) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let u = user as i64;
+    let c = channel as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO tagpings (serverid, tagid, tagname, userid, channelid) VALUES (?, ?, ?, ?, ?)",
+            g,
+            tag.tagid,
+            tag.tagname,
+            u,
+            c
+        )
+        .execute(conn),
+        "Logging a ping of tag {} by user ID {} in guild ID {}",
+        "Failed to log a ping of tag {} by user ID {} in guild ID {}",
+        tag.tagname,
+        user,
+        guild
+    )
+}
+
+/// # last_tag_ping
+/// Gets when a tag was last pinged, or 0 if it never was.
+pub async fn last_tag_ping(conn: &Pool, tagid: i64) -> ZweiDbRes<i64> {
+    query!(
+        r#"SELECT MAX(pinged) AS "last: i64" FROM tagpings WHERE tagid = ?"#,
+        tagid
+    )
+    .fetch_one(conn)
+    .await
+    .map(|row| row.last.unwrap_or(0))
+}
+
+/// # user_pings_since
+/// Gets when a member last pinged any tag in this guild, or 0 if they never
+/// did, along with how many tags they pinged since the given time.
+pub async fn user_pings_since(
+    conn: &Pool,
+    guild: u64,
+    user: u64,
+    since: i64,
+) -> ZweiDbRes<(i64, i64)> {
+    let g = guild as i64;
+    let u = user as i64;
+    query!(
+        r#"SELECT MAX(pinged) AS "last: i64", COUNT(CASE WHEN pinged > ? THEN 1 END) AS "count!: i64" FROM tagpings WHERE serverid = ? AND userid = ?"#,
+        since,
         g,
         u
     )
     .fetch_one(conn)
+    .await
+    .map(|row| (row.last.unwrap_or(0), row.count))
+}
+
+/// # TagPing
+/// A single entry in the log of tag pings.
+pub struct TagPing {
+    pub tagname: String,
+    pub userid: i64,
+    pub channelid: i64,
+    pub pinged: i64,
+}
+
+/// # get_tag_pings
+/// Fetches the latest pings in this guild, newest first, optionally only those
+/// of a single tag.
+pub async fn get_tag_pings(
+    conn: &Pool,
+    guild: u64,
+    tag: Option<&String>,
+    limit: i64,
+) -> ZweiDbRes<Vec<TagPing>> {
+    let g = guild as i64;
+    query_as!(
+        TagPing,
+        "SELECT tagname, userid, channelid, pinged FROM tagpings WHERE serverid = ? AND (? IS NULL OR tagname = ?) ORDER BY pingid DESC LIMIT ?",
+        g,
+        tag,
+        tag,
+        limit
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # Warning
+/// A single warning as stored in the `warnings` table.
+pub struct Warning {
+    pub warnid: i64,
+    pub moderator: i64,
+    pub issued: i64,
+    pub message: String,
+}
+
+/// # add_warning
+/// Stores a warning for a user in this guild, returning the amount of active
+/// warnings they've collected here so far. This includes the one that was just
+/// added, but skips any that expired according to [`warn_cutoff`].
+pub async fn add_warning(
+    conn: &Pool,
+    guild: u64,
+    user: u64,
+    moderator: u64,
+    msg: &str,
+) -> ZweiDbRes<i32> {
+    let g = guild as i64;
+    let u = user as i64;
+    let m = moderator as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO warnings (serverid, userid, moderator, issued, message) VALUES (?, ?, ?, strftime('%s', 'now'), ?)",
+            g,
+            u,
+            m,
+            msg
+        )
+        .execute(conn),
+        "Warning user ID {} in guild ID {}",
+        "Failed to store a warning for user ID {} in guild ID {}",
+        user,
+        guild
+    )?;
+    let cutoff = warn_cutoff(conn, guild).await?;
+    Ok(query!(
+        "SELECT COUNT(warnid) AS warncount FROM warnings WHERE serverid = ? AND userid = ? AND issued >= ?",
+        g,
+        u,
+        cutoff
+    )
+    .fetch_one(conn)
     .await?
     .warncount)
 }
+
+/// # get_warnings
+/// Fetches all warnings a user received in this guild, oldest first.
+/// The returned Vec is empty for users with a clean record.
+pub async fn get_warnings(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<Vec<Warning>> {
+    let g = guild as i64;
+    let u = user as i64;
+    query_as!(
+        Warning,
+        "SELECT warnid, moderator, issued, message FROM warnings WHERE serverid = ? AND userid = ? ORDER BY warnid",
+        g,
+        u
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # remove_warning
+/// Deletes a single warning by its ID. The guild is part of the check so one
+/// server can't remove the warnings handed out in another.
+pub async fn remove_warning(conn: &Pool, guild: u64, warnid: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM warnings WHERE warnid = ? AND serverid = ?",
+            warnid,
+            g
+        )
+        .execute(conn),
+        "Removing warning {} for guild ID {}",
+        "Failed to remove warning {} for guild ID {}",
+        warnid,
+        guild
+    )
+}
+
+/// # clear_warnings
+/// Wipes the slate clean for a user in this guild, returning how many
+/// warnings were removed.
+pub async fn clear_warnings(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let u = user as i64;
+    query!(
+        "DELETE FROM escalated WHERE serverid = ? AND userid = ?",
+        g,
+        u
+    )
+    .execute(conn)
+    .await?;
+    rowcount!(
+        query!(
+            "DELETE FROM warnings WHERE serverid = ? AND userid = ?",
+            g,
+            u
+        )
+        .execute(conn),
+        "Clearing warnings for user ID {} in guild ID {}",
+        "Failed to clear warnings for user ID {} in guild ID {}",
+        user,
+        guild
+    )
+}
+
+/// # Note
+/// A private note on a user as stored in the `notes` table.
+pub struct Note {
+    pub noteid: i64,
+    pub author: i64,
+    pub created: i64,
+    pub note: String,
+}
+
+/// # add_note
+/// Stores a private note on a user in this guild, returning the ID it can be
+/// removed by.
+pub async fn add_note(
+    conn: &Pool,
+    guild: u64,
+    user: u64,
+    author: u64,
+    note: &str,
+) -> ZweiDbRes<i64> {
+    let g = guild as i64;
+    let u = user as i64;
+    let a = author as i64;
+    let noteid = query!(
+        "INSERT INTO notes (serverid, userid, author, note) VALUES (?, ?, ?, ?)",
+        g,
+        u,
+        a,
+        note
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to store a note on user ID {user} in guild ID {guild}\n\t{e}");
+        e
+    })?
+    .last_insert_rowid();
+    trace!("Stored note {noteid} on user ID {user} in guild ID {guild}");
+    Ok(noteid)
+}
+
+/// # get_notes
+/// Fetches all notes on a user in this guild, oldest first.
+pub async fn get_notes(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<Vec<Note>> {
+    let g = guild as i64;
+    let u = user as i64;
+    query_as!(
+        Note,
+        "SELECT noteid, author, created, note FROM notes WHERE serverid = ? AND userid = ? ORDER BY noteid",
+        g,
+        u
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # remove_note
+/// Deletes a single note by its ID. The guild is part of the check so one
+/// server can't remove the notes kept in another.
+pub async fn remove_note(conn: &Pool, guild: u64, noteid: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM notes WHERE noteid = ? AND serverid = ?",
+            noteid,
+            g
+        )
+        .execute(conn),
+        "Removing note {} for guild ID {}",
+        "Failed to remove note {} for guild ID {}",
+        noteid,
+        guild
+    )
+}
+
+/// # get_warn_expiry
+/// Gets the amount of days after which warnings stop counting in this guild.
+/// Guilds that never configured this get 0, meaning warnings never expire.
+pub async fn get_warn_expiry(conn: &Pool, guild: u64) -> ZweiDbRes<i64> {
+    let g = guild as i64;
+    query!("SELECT warn_expiry FROM modconf WHERE serverid = ?", g)
+        .fetch_optional(conn)
+        .await
+        .map(|res| res.map(|row| row.warn_expiry).unwrap_or(0))
+}
+
+/// # set_warn_expiry
+/// Sets the amount of days after which warnings stop counting in this guild.
+/// Setting it to 0 makes warnings last forever.
+pub async fn set_warn_expiry(conn: &Pool, guild: u64, days: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO modconf (serverid, warn_expiry) VALUES (?, ?) ON CONFLICT(serverid) DO UPDATE SET warn_expiry = excluded.warn_expiry",
+            g,
+            days
+        )
+        .execute(conn),
+        "Setting warning expiry to {} days for guild ID {}",
+        "Failed to set warning expiry to {} days for guild ID {}",
+        days,
+        guild
+    )
+}
+
+/// # warn_cutoff
+/// Computes the UNIX timestamp before which warnings no longer count in this
+/// guild. Returns 0 if warnings don't expire here.
+pub async fn warn_cutoff(conn: &Pool, guild: u64) -> ZweiDbRes<i64> {
+    let days = get_warn_expiry(conn, guild).await?;
+    Ok(if days > 0 {
+        Utc::now().timestamp() - days * 86400
+    } else {
+        0
+    })
+}
+
+/// # Escalation
+/// An action to take once a user collects a certain amount of warnings.
+/// The duration is in seconds and only has meaning for timeouts.
+pub struct Escalation {
+    pub threshold: i64,
+    pub action: String,
+    pub duration: i64,
+}
+
+/// # get_escalations
+/// Fetches all escalation rules for this guild, ordered by their threshold.
+pub async fn get_escalations(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<Escalation>> {
+    let g = guild as i64;
+    query_as!(
+        Escalation,
+        "SELECT threshold, action, duration FROM escalations WHERE serverid = ? ORDER BY threshold",
+        g
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # get_escalation
+/// Fetches the highest escalation rule a member with this many warnings has
+/// reached but not had applied yet, if there is one. Rules at or below the
+/// last applied threshold are skipped, so warnings dropping and coming back
+/// don't trigger the same rule twice.
+pub async fn get_escalation(
+    conn: &Pool,
+    guild: u64,
+    user: u64,
+    count: i32,
+) -> ZweiDbRes<Option<Escalation>> {
+    let g = guild as i64;
+    let u = user as i64;
+    query_as!(
+        Escalation,
+        "SELECT threshold, action, duration FROM escalations WHERE serverid = ? AND threshold <= ? AND threshold > COALESCE((SELECT threshold FROM escalated WHERE serverid = ? AND userid = ?), 0) ORDER BY threshold DESC LIMIT 1",
+        g,
+        count,
+        g,
+        u
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// # set_escalated
+/// Remembers the threshold of the escalation rule last applied to a member.
+pub async fn set_escalated(conn: &Pool, guild: u64, user: u64, threshold: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let u = user as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO escalated (serverid, userid, threshold) VALUES (?, ?, ?)",
+            g,
+            u,
+            threshold
+        )
+        .execute(conn),
+        "Marking escalation {} as applied to user ID {} in guild ID {}",
+        "Failed to mark escalation {} as applied to user ID {} in guild ID {}",
+        threshold,
+        user,
+        guild
+    )
+}
+
+/// # set_escalation
+/// Registers an escalation rule for this guild. Any existing rule for the same
+/// threshold is replaced.
+pub async fn set_escalation(
+    conn: &Pool,
+    guild: u64,
+    threshold: i64,
+    action: &str,
+    duration: i64,
+) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO escalations (serverid, threshold, action, duration) VALUES (?, ?, ?, ?)",
+            g,
+            threshold,
+            action,
+            duration
+        )
+        .execute(conn),
+        "Setting escalation at {} warnings to {} for guild ID {}",
+        "Failed to set escalation at {} warnings to {} for guild ID {}",
+        threshold,
+        action,
+        guild
+    )
+}
+
+/// # remove_escalation
+/// Removes the escalation rule for a threshold in this guild, if present.
+pub async fn remove_escalation(conn: &Pool, guild: u64, threshold: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM escalations WHERE serverid = ? AND threshold = ?",
+            g,
+            threshold
+        )
+        .execute(conn),
+        "Removing escalation at {} warnings for guild ID {}",
+        "Failed to remove escalation at {} warnings for guild ID {}",
+        threshold,
+        guild
+    )
+}
+
+/// # ScheduledAction
+/// An action the scheduler should perform once `due` (a UNIX timestamp) has
+/// passed. What `target` and `data` refer to depends on the kind of `action`.
+/// `attempts` counts how often performing it failed before.
+#[derive(Clone, Debug)]
+pub struct ScheduledAction {
+    pub actionid: i64,
+    pub serverid: i64,
+    pub target: i64,
+    pub action: String,
+    pub due: i64,
+    pub data: i64,
+    pub attempts: i64,
+}
+
+/// # add_scheduled
+/// Stores an action to be performed later, returning the stored action so it
+/// can be handed to the scheduler.
+pub async fn add_scheduled(
+    conn: &Pool,
+    guild: u64,
+    target: u64,
+    action: &str,
+    due: i64,
+    data: i64,
+) -> ZweiDbRes<ScheduledAction> {
+    let g = guild as i64;
+    let t = target as i64;
+    let actionid = query!(
+        "INSERT INTO scheduled_actions (serverid, target, action, due, data) VALUES (?, ?, ?, ?, ?)",
+        g,
+        t,
+        action,
+        due,
+        data
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to schedule {action} on {target} for guild ID {guild}\n\t{e}");
+        e
+    })?
+    .last_insert_rowid();
+    trace!("Scheduled {action} on {target} for guild ID {guild} as action {actionid}");
+    Ok(ScheduledAction {
+        actionid,
+        serverid: g,
+        target: t,
+        action: action.to_owned(),
+        due,
+        data,
+        attempts: 0,
+    })
+}
+
+/// # get_scheduled
+/// Fetches every pending action across all guilds. Used to refill the
+/// scheduler after a restart.
+pub async fn get_scheduled(conn: &Pool) -> ZweiDbRes<Vec<ScheduledAction>> {
+    query_as!(
+        ScheduledAction,
+        "SELECT actionid, serverid, target, action, due, data, attempts FROM scheduled_actions"
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # get_pending
+/// Fetches the first pending action of a kind for a target in this guild, if
+/// there is one. Used to find what a temporary change will go back to.
+pub async fn get_pending(
+    conn: &Pool,
+    guild: u64,
+    target: u64,
+    action: &str,
+) -> ZweiDbRes<Option<ScheduledAction>> {
+    let g = guild as i64;
+    let t = target as i64;
+    query_as!(
+        ScheduledAction,
+        "SELECT actionid, serverid, target, action, due, data, attempts FROM scheduled_actions WHERE serverid = ? AND target = ? AND action = ? ORDER BY due LIMIT 1",
+        g,
+        t,
+        action
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// # claim_scheduled
+/// Removes a pending action right before it's performed. Returns 0 if it was
+/// cancelled in the meantime, in which case it should be skipped.
+pub async fn claim_scheduled(conn: &Pool, actionid: i64) -> ZweiDbRes<u64> {
+    rowcount!(
+        query!("DELETE FROM scheduled_actions WHERE actionid = ?", actionid).execute(conn),
+        "Claiming scheduled action {}",
+        "Failed to claim scheduled action {}",
+        actionid
+    )
+}
+
+/// # retry_scheduled
+/// Stores a claimed action again after performing it failed, to be tried once
+/// more at `due`. Returns the stored action so it can go back to the
+/// scheduler.
+pub async fn retry_scheduled(
+    conn: &Pool,
+    action: &ScheduledAction,
+    due: i64,
+) -> ZweiDbRes<ScheduledAction> {
+    let attempts = action.attempts + 1;
+    let actionid = query!(
+        "INSERT INTO scheduled_actions (serverid, target, action, due, data, attempts) VALUES (?, ?, ?, ?, ?, ?)",
+        action.serverid,
+        action.target,
+        action.action,
+        due,
+        action.data,
+        attempts
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to retry scheduled action {}\n\t{e}", action.actionid);
+        e
+    })?
+    .last_insert_rowid();
+    trace!(
+        "Retrying scheduled action {} as action {actionid}",
+        action.actionid
+    );
+    Ok(ScheduledAction {
+        actionid,
+        due,
+        attempts,
+        ..action.clone()
+    })
+}
+
+/// # cancel_scheduled
+/// Cancels all pending actions of a kind for a target in this guild, like the
+/// unban for someone whose temporary ban was lifted by hand.
+pub async fn cancel_scheduled(
+    conn: &Pool,
+    guild: u64,
+    target: u64,
+    action: &str,
+) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let t = target as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM scheduled_actions WHERE serverid = ? AND target = ? AND action = ?",
+            g,
+            t,
+            action
+        )
+        .execute(conn),
+        "Cancelling {} on {} for guild ID {}",
+        "Failed to cancel {} on {} for guild ID {}",
+        action,
+        target,
+        guild
+    )
+}
+
+/// # get_mute_role
+/// Gets the role this guild uses to mute people. Returns 0 if none is set.
+pub async fn get_mute_role(conn: &Pool, guild: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    query!("SELECT muterole FROM modconf WHERE serverid = ?", g)
+        .fetch_optional(conn)
+        .await
+        .map(|res| res.map(|row| row.muterole as u64).unwrap_or(0))
+}
+
+/// # set_mute_role
+/// Sets the role this guild uses to mute people. Use 0 to unset it.
+pub async fn set_mute_role(conn: &Pool, guild: u64, role: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let r = role as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO modconf (serverid, muterole) VALUES (?, ?) ON CONFLICT(serverid) DO UPDATE SET muterole = excluded.muterole",
+            g,
+            r
+        )
+        .execute(conn),
+        "Setting mute role {} for guild ID {}",
+        "Failed to set mute role {} for guild ID {}",
+        role,
+        guild
+    )
+}
+
+/// # add_mute
+/// Remembers that a user was muted with the muted role in this guild.
+pub async fn add_mute(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let u = user as i64;
+    rowcount!(
+        query!("INSERT INTO mutes (serverid, userid) VALUES (?, ?)", g, u).execute(conn),
+        "Muting user ID {} in guild ID {}",
+        "Failed to store the mute for user ID {} in guild ID {}",
+        user,
+        guild
+    )
+}
+
+/// # remove_mute
+/// Forgets about a user's mute in this guild. Returns 0 if they weren't muted
+/// with the muted role.
+pub async fn remove_mute(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let u = user as i64;
+    rowcount!(
+        query!("DELETE FROM mutes WHERE serverid = ? AND userid = ?", g, u).execute(conn),
+        "Unmuting user ID {} in guild ID {}",
+        "Failed to remove the mute for user ID {} in guild ID {}",
+        user,
+        guild
+    )
+}
+
+/// # is_muted
+/// Checks whether a user is muted with the muted role in this guild.
+pub async fn is_muted(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<bool> {
+    let g = guild as i64;
+    let u = user as i64;
+    query!(
+        "SELECT userid FROM mutes WHERE serverid = ? AND userid = ?",
+        g,
+        u
+    )
+    .fetch_optional(conn)
+    .await
+    .map(|res| res.is_some())
+}
+
+/// # get_modlog
+/// Gets the channel moderation cases are posted to in this guild.
+/// Returns 0 if none is set.
+pub async fn get_modlog(conn: &Pool, guild: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    query!("SELECT modlog FROM modconf WHERE serverid = ?", g)
+        .fetch_optional(conn)
+        .await
+        .map(|res| res.map(|row| row.modlog as u64).unwrap_or(0))
+}
+
+/// # set_modlog
+/// Sets the channel moderation cases are posted to. Use 0 to unset it.
+pub async fn set_modlog(conn: &Pool, guild: u64, channel: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let c = channel as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO modconf (serverid, modlog) VALUES (?, ?) ON CONFLICT(serverid) DO UPDATE SET modlog = excluded.modlog",
+            g,
+            c
+        )
+        .execute(conn),
+        "Setting mod-log channel {} for guild ID {}",
+        "Failed to set mod-log channel {} for guild ID {}",
+        channel,
+        guild
+    )
+}
+
+/// # get_appeal_channel
+/// Gets the channel ban appeals are posted to in this guild.
+/// Returns 0 if none is set, meaning bans can't be appealed.
+pub async fn get_appeal_channel(conn: &Pool, guild: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    query!("SELECT appeals FROM modconf WHERE serverid = ?", g)
+        .fetch_optional(conn)
+        .await
+        .map(|res| res.map(|row| row.appeals as u64).unwrap_or(0))
+}
+
+/// # set_appeal_channel
+/// Sets the channel ban appeals are posted to. Use 0 to unset it.
+pub async fn set_appeal_channel(conn: &Pool, guild: u64, channel: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let c = channel as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO modconf (serverid, appeals) VALUES (?, ?) ON CONFLICT(serverid) DO UPDATE SET appeals = excluded.appeals",
+            g,
+            c
+        )
+        .execute(conn),
+        "Setting appeals channel {} for guild ID {}",
+        "Failed to set appeals channel {} for guild ID {}",
+        channel,
+        guild
+    )
+}
+
+/// # Case
+/// A numbered moderation case. `caseno` counts up per guild, and `logmsg` is
+/// the message in the mod-log channel showing this case, or 0 if there is none.
+pub struct Case {
+    pub caseno: i64,
+    pub moderator: i64,
+    pub target: i64,
+    pub action: String,
+    pub reason: String,
+    pub created: i64,
+    pub logmsg: i64,
+}
+
+/// # add_case
+/// Opens a new case in this guild, numbered one higher than the last one.
+pub async fn add_case(
+    conn: &Pool,
+    guild: u64,
+    moderator: u64,
+    target: u64,
+    action: &str,
+    reason: &str,
+) -> ZweiDbRes<Case> {
+    let g = guild as i64;
+    let m = moderator as i64;
+    let t = target as i64;
+    let caseid = query!(
+        "INSERT INTO cases (serverid, caseno, moderator, target, action, reason) VALUES (?, (SELECT COALESCE(MAX(caseno), 0) + 1 FROM cases WHERE serverid = ?), ?, ?, ?, ?)",
+        g,
+        g,
+        m,
+        t,
+        action,
+        reason
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to open a {action} case on {target} for guild ID {guild}\n\t{e}");
+        e
+    })?
+    .last_insert_rowid();
+    trace!("Opened case {caseid}, {action} on {target} for guild ID {guild}");
+    query_as!(
+        Case,
+        "SELECT caseno, moderator, target, action, reason, created, logmsg FROM cases WHERE caseid = ?",
+        caseid
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// # get_case
+/// Fetches a case by its number in this guild, if it exists.
+pub async fn get_case(conn: &Pool, guild: u64, caseno: i64) -> ZweiDbRes<Option<Case>> {
+    let g = guild as i64;
+    query_as!(
+        Case,
+        "SELECT caseno, moderator, target, action, reason, created, logmsg FROM cases WHERE serverid = ? AND caseno = ?",
+        g,
+        caseno
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// # get_user_cases
+/// Fetches every case involving a user in this guild, oldest first.
+pub async fn get_user_cases(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<Vec<Case>> {
+    let g = guild as i64;
+    let u = user as i64;
+    query_as!(
+        Case,
+        "SELECT caseno, moderator, target, action, reason, created, logmsg FROM cases WHERE serverid = ? AND target = ? ORDER BY caseno",
+        g,
+        u
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # set_case_reason
+/// Amends the reason for a case in this guild. Returns 0 if there's no such case.
+pub async fn set_case_reason(conn: &Pool, guild: u64, caseno: i64, reason: &str) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "UPDATE cases SET reason = ? WHERE serverid = ? AND caseno = ?",
+            reason,
+            g,
+            caseno
+        )
+        .execute(conn),
+        "Amending the reason for case {} in guild ID {}",
+        "Failed to amend the reason for case {} in guild ID {}",
+        caseno,
+        guild
+    )
+}
+
+/// # set_case_logmsg
+/// Remembers which mod-log message shows this case, so it can be edited later.
+pub async fn set_case_logmsg(conn: &Pool, guild: u64, caseno: i64, logmsg: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let l = logmsg as i64;
+    rowcount!(
+        query!(
+            "UPDATE cases SET logmsg = ? WHERE serverid = ? AND caseno = ?",
+            l,
+            g,
+            caseno
+        )
+        .execute(conn),
+        "Storing the mod-log message for case {} in guild ID {}",
+        "Failed to store the mod-log message for case {} in guild ID {}",
+        caseno,
+        guild
+    )
+}
+
+/// # AntiRaid
+/// A guild's anti-raid settings. Raid mode trips when `joins` members join
+/// within `per_secs` seconds, with 0 joins meaning it never trips on its own.
+/// Accounts younger than `min_age` seconds are flagged when they join.
+/// `prev_level` is the verification level to restore once raid mode is lifted.
+#[derive(Default)]
+pub struct AntiRaid {
+    pub joins: i64,
+    pub per_secs: i64,
+    pub min_age: i64,
+    pub active: bool,
+    pub prev_level: i64,
+}
+
+/// # get_antiraid
+/// Gets the anti-raid settings for this guild, or the defaults if it never
+/// configured them.
+pub async fn get_antiraid(conn: &Pool, guild: u64) -> ZweiDbRes<AntiRaid> {
+    let g = guild as i64;
+    query_as!(
+        AntiRaid,
+        r#"SELECT joins, per_secs, min_age, active AS "active: bool", prev_level FROM antiraid WHERE serverid = ?"#,
+        g
+    )
+    .fetch_optional(conn)
+    .await
+    .map(|res| {
+        res.unwrap_or(AntiRaid {
+            per_secs: 10,
+            ..Default::default()
+        })
+    })
+}
+
+/// # set_raid_threshold
+/// Sets how many joins within how many seconds trip raid mode in this guild.
+/// Use 0 joins to stop tracking joins.
+pub async fn set_raid_threshold(
+    conn: &Pool,
+    guild: u64,
+    joins: i64,
+    per_secs: i64,
+) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO antiraid (serverid, joins, per_secs) VALUES (?, ?, ?) ON CONFLICT(serverid) DO UPDATE SET joins = excluded.joins, per_secs = excluded.per_secs",
+            g,
+            joins,
+            per_secs
+        )
+        .execute(conn),
+        "Setting the raid threshold to {} joins in {}s for guild ID {}",
+        "Failed to set the raid threshold to {} joins in {}s for guild ID {}",
+        joins,
+        per_secs,
+        guild
+    )
+}
+
+/// # set_raid_min_age
+/// Sets the account age in seconds below which joining accounts get flagged.
+/// Use 0 to stop flagging new accounts.
+pub async fn set_raid_min_age(conn: &Pool, guild: u64, secs: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO antiraid (serverid, min_age) VALUES (?, ?) ON CONFLICT(serverid) DO UPDATE SET min_age = excluded.min_age",
+            g,
+            secs
+        )
+        .execute(conn),
+        "Setting the minimum account age to {}s for guild ID {}",
+        "Failed to set the minimum account age to {}s for guild ID {}",
+        secs,
+        guild
+    )
+}
+
+/// # set_raid_mode
+/// Turns raid mode on or off for this guild, remembering the verification
+/// level that was in place before it was turned on. Nothing changes if raid
+/// mode already is in the requested state, so turning it on twice doesn't
+/// overwrite the level to go back to. Returns 0 in that case.
+pub async fn set_raid_mode(
+    conn: &Pool,
+    guild: u64,
+    active: bool,
+    prev_level: i64,
+) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO antiraid (serverid, active, prev_level) VALUES (?, ?, ?) ON CONFLICT(serverid) DO UPDATE SET active = excluded.active, prev_level = excluded.prev_level WHERE active != excluded.active",
+            g,
+            active,
+            prev_level
+        )
+        .execute(conn),
+        "Setting raid mode to {} for guild ID {}",
+        "Failed to set raid mode to {} for guild ID {}",
+        active,
+        guild
+    )
+}
+
+/// # ChannelLock
+/// A locked channel along with the `@everyone` overwrite it had before it was
+/// locked. If `existed` is false, there was no overwrite to begin with.
+pub struct ChannelLock {
+    pub channelid: i64,
+    pub serverid: i64,
+    pub allow_bits: i64,
+    pub deny_bits: i64,
+    pub existed: bool,
+}
+
+/// # add_lock
+/// Remembers the original `@everyone` overwrite of a channel that's about to
+/// be locked.
+pub async fn add_lock(
+    conn: &Pool,
+    guild: u64,
+    channel: u64,
+    allow: u64,
+    deny: u64,
+    existed: bool,
+) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let c = channel as i64;
+    let a = allow as i64;
+    let d = deny as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO locks (channelid, serverid, allow_bits, deny_bits, existed) VALUES (?, ?, ?, ?, ?)",
+            c,
+            g,
+            a,
+            d,
+            existed
+        )
+        .execute(conn),
+        "Storing the lock on channel ID {} in guild ID {}",
+        "Failed to store the lock on channel ID {} in guild ID {}",
+        channel,
+        guild
+    )
+}
+
+/// # get_lock
+/// Fetches the stored lock for a channel, if it's locked.
+pub async fn get_lock(conn: &Pool, guild: u64, channel: u64) -> ZweiDbRes<Option<ChannelLock>> {
+    let g = guild as i64;
+    let c = channel as i64;
+    query_as!(
+        ChannelLock,
+        r#"SELECT channelid, serverid, allow_bits, deny_bits, existed AS "existed: bool" FROM locks WHERE serverid = ? AND channelid = ?"#,
+        g,
+        c
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// # get_locks
+/// Fetches every locked channel in this guild.
+pub async fn get_locks(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<ChannelLock>> {
+    let g = guild as i64;
+    query_as!(
+        ChannelLock,
+        r#"SELECT channelid, serverid, allow_bits, deny_bits, existed AS "existed: bool" FROM locks WHERE serverid = ?"#,
+        g
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # remove_lock
+/// Forgets about a channel's lock once it's lifted. Returns 0 if the channel
+/// wasn't locked.
+pub async fn remove_lock(conn: &Pool, guild: u64, channel: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let c = channel as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM locks WHERE serverid = ? AND channelid = ?",
+            g,
+            c
+        )
+        .execute(conn),
+        "Removing the lock on channel ID {} in guild ID {}",
+        "Failed to remove the lock on channel ID {} in guild ID {}",
+        channel,
+        guild
+    )
+}
+
+/// # AutomodRule
+/// A single automod rule. What `pattern` means depends on the `kind`, and
+/// `duration` is only used when the `action` is a mute.
+#[derive(Clone)]
+pub struct AutomodRule {
+    pub ruleid: i64,
+    pub kind: String,
+    pub pattern: String,
+    pub action: String,
+    pub duration: i64,
+}
+
+/// # get_automod_rules
+/// Fetches all automod rules for this guild, in the order they were added.
+pub async fn get_automod_rules(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<AutomodRule>> {
+    let g = guild as i64;
+    query_as!(
+        AutomodRule,
+        "SELECT ruleid, kind, pattern, action, duration FROM automod_rules WHERE serverid = ? ORDER BY ruleid",
+        g
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # add_automod_rule
+/// Adds an automod rule to this guild, returning the ID it can be removed by.
+pub async fn add_automod_rule(
+    conn: &Pool,
+    guild: u64,
+    kind: &str,
+    pattern: &str,
+    action: &str,
+    duration: i64,
+) -> ZweiDbRes<i64> {
+    let g = guild as i64;
+    let ruleid = query!(
+        "INSERT INTO automod_rules (serverid, kind, pattern, action, duration) VALUES (?, ?, ?, ?, ?)",
+        g,
+        kind,
+        pattern,
+        action,
+        duration
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to add {kind} automod rule for guild ID {guild}\n\t{e}");
+        e
+    })?
+    .last_insert_rowid();
+    trace!("Added {kind} automod rule {ruleid} for guild ID {guild}");
+    Ok(ruleid)
+}
+
+/// # remove_automod_rule
+/// Removes an automod rule from this guild. Returns 0 if it didn't exist.
+pub async fn remove_automod_rule(conn: &Pool, guild: u64, ruleid: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM automod_rules WHERE serverid = ? AND ruleid = ?",
+            g,
+            ruleid
+        )
+        .execute(conn),
+        "Removing automod rule {} for guild ID {}",
+        "Failed to remove automod rule {} for guild ID {}",
+        ruleid,
+        guild
+    )
+}
+
+/// # get_automod_exempt
+/// Fetches the roles automod leaves alone in this guild.
+pub async fn get_automod_exempt(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<u64>> {
+    let g = guild as i64;
+    query!("SELECT roleid FROM automod_exempt WHERE serverid = ?", g)
+        .fetch_all(conn)
+        .await
+        .map(|rows| rows.iter().map(|row| row.roleid as u64).collect())
+}
+
+/// # add_automod_exempt
+/// Makes automod leave members with this role alone.
+pub async fn add_automod_exempt(conn: &Pool, guild: u64, role: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let r = role as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO automod_exempt (serverid, roleid) VALUES (?, ?)",
+            g,
+            r
+        )
+        .execute(conn),
+        "Exempting role ID {} from automod in guild ID {}",
+        "Failed to exempt role ID {} from automod in guild ID {}",
+        role,
+        guild
+    )
+}
+
+/// # remove_automod_exempt
+/// Makes automod check members with this role again. Returns 0 if the role
+/// wasn't exempt.
+pub async fn remove_automod_exempt(conn: &Pool, guild: u64, role: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let r = role as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM automod_exempt WHERE serverid = ? AND roleid = ?",
+            g,
+            r
+        )
+        .execute(conn),
+        "Removing the automod exemption for role ID {} in guild ID {}",
+        "Failed to remove the automod exemption for role ID {} in guild ID {}",
+        role,
+        guild
+    )
+}
+
+/// # AntiSpam
+/// A guild's anti-spam settings. Someone is spamming when they send
+/// `max_msgs` messages or `max_dupes` identical messages within `per_secs`
+/// seconds, or `max_mentions` mentions in one message. A limit of 0 turns that
+/// check off. `duration` is only used when the `action` is a mute.
+#[derive(Clone)]
+pub struct AntiSpam {
+    pub max_msgs: i64,
+    pub max_dupes: i64,
+    pub max_mentions: i64,
+    pub per_secs: i64,
+    pub action: String,
+    pub duration: i64,
+}
+
+impl Default for AntiSpam {
+    fn default() -> Self {
+        Self {
+            max_msgs: 0,
+            max_dupes: 0,
+            max_mentions: 0,
+            per_secs: 5,
+            action: "mute".to_owned(),
+            duration: 600,
+        }
+    }
+}
+
+/// # get_antispam
+/// Gets the anti-spam settings for this guild, or the defaults if it never
+/// configured them.
+pub async fn get_antispam(conn: &Pool, guild: u64) -> ZweiDbRes<AntiSpam> {
+    let g = guild as i64;
+    query_as!(
+        AntiSpam,
+        "SELECT max_msgs, max_dupes, max_mentions, per_secs, action, duration FROM antispam WHERE serverid = ?",
+        g
+    )
+    .fetch_optional(conn)
+    .await
+    .map(Option::unwrap_or_default)
+}
+
+/// # set_antispam
+/// Stores all anti-spam settings for this guild at once.
+pub async fn set_antispam(conn: &Pool, guild: u64, conf: &AntiSpam) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO antispam (serverid, max_msgs, max_dupes, max_mentions, per_secs, action, duration) VALUES (?, ?, ?, ?, ?, ?, ?) ON CONFLICT(serverid) DO UPDATE SET max_msgs = excluded.max_msgs, max_dupes = excluded.max_dupes, max_mentions = excluded.max_mentions, per_secs = excluded.per_secs, action = excluded.action, duration = excluded.duration",
+            g,
+            conf.max_msgs,
+            conf.max_dupes,
+            conf.max_mentions,
+            conf.per_secs,
+            conf.action,
+            conf.duration
+        )
+        .execute(conn),
+        "Storing anti-spam settings for guild ID {}",
+        "Failed to store anti-spam settings for guild ID {}",
+        guild
+    )
+}
+
+/// # get_antispam_exempt
+/// Fetches the channels anti-spam leaves alone in this guild.
+pub async fn get_antispam_exempt(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<u64>> {
+    let g = guild as i64;
+    query!(
+        "SELECT channelid FROM antispam_exempt WHERE serverid = ?",
+        g
+    )
+    .fetch_all(conn)
+    .await
+    .map(|rows| rows.iter().map(|row| row.channelid as u64).collect())
+}
+
+/// # add_antispam_exempt
+/// Makes anti-spam leave this channel alone.
+pub async fn add_antispam_exempt(conn: &Pool, guild: u64, channel: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let c = channel as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO antispam_exempt (serverid, channelid) VALUES (?, ?)",
+            g,
+            c
+        )
+        .execute(conn),
+        "Exempting channel ID {} from anti-spam in guild ID {}",
+        "Failed to exempt channel ID {} from anti-spam in guild ID {}",
+        channel,
+        guild
+    )
+}
+
+/// # remove_antispam_exempt
+/// Makes anti-spam check this channel again. Returns 0 if the channel wasn't
+/// exempt.
+pub async fn remove_antispam_exempt(conn: &Pool, guild: u64, channel: u64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    let c = channel as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM antispam_exempt WHERE serverid = ? AND channelid = ?",
+            g,
+            c
+        )
+        .execute(conn),
+        "Removing the anti-spam exemption for channel ID {} in guild ID {}",
+        "Failed to remove the anti-spam exemption for channel ID {} in guild ID {}",
+        channel,
+        guild
+    )
+}
+
+/// # NameRules
+/// A guild's rules for display names. With `ascii` on, names need at least one
+/// ASCII letter or digit so they can be typed. With `zalgo` on, zalgo names
+/// aren't allowed. Breaking a rule gets a member renamed to `placeholder`.
+/// With `dehoist` on, leading symbols are stripped from names.
+#[derive(Clone)]
+pub struct NameRules {
+    pub placeholder: String,
+    pub dehoist: bool,
+    pub ascii: bool,
+    pub zalgo: bool,
+}
+
+impl Default for NameRules {
+    fn default() -> Self {
+        Self {
+            placeholder: "Moderated Nickname".to_owned(),
+            dehoist: false,
+            ascii: false,
+            zalgo: false,
+        }
+    }
+}
+
+/// # get_namerules
+/// Gets the name rules for this guild, or the defaults if it never configured
+/// them.
+pub async fn get_namerules(conn: &Pool, guild: u64) -> ZweiDbRes<NameRules> {
+    let g = guild as i64;
+    query_as!(
+        NameRules,
+        r#"SELECT placeholder, dehoist AS "dehoist: bool", ascii AS "ascii: bool", zalgo AS "zalgo: bool" FROM namerules WHERE serverid = ?"#,
+        g
+    )
+    .fetch_optional(conn)
+    .await
+    .map(Option::unwrap_or_default)
+}
+
+/// # set_namerules
+/// Stores all name rules for this guild at once.
+pub async fn set_namerules(conn: &Pool, guild: u64, rules: &NameRules) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "INSERT INTO namerules (serverid, placeholder, dehoist, ascii, zalgo) VALUES (?, ?, ?, ?, ?) ON CONFLICT(serverid) DO UPDATE SET placeholder = excluded.placeholder, dehoist = excluded.dehoist, ascii = excluded.ascii, zalgo = excluded.zalgo",
+            g,
+            rules.placeholder,
+            rules.dehoist,
+            rules.ascii,
+            rules.zalgo
+        )
+        .execute(conn),
+        "Storing name rules for guild ID {}",
+        "Failed to store name rules for guild ID {}",
+        guild
+    )
+}
+
+/// # DeniedName
+/// A pattern display names in a guild may not match.
+pub struct DeniedName {
+    pub patternid: i64,
+    pub pattern: String,
+}
+
+/// # get_name_denylist
+/// Fetches the patterns display names may not match in this guild.
+pub async fn get_name_denylist(conn: &Pool, guild: u64) -> ZweiDbRes<Vec<DeniedName>> {
+    let g = guild as i64;
+    query_as!(
+        DeniedName,
+        "SELECT patternid, pattern FROM name_denylist WHERE serverid = ? ORDER BY patternid",
+        g
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// # add_name_denied
+/// Adds a pattern display names may not match, returning the ID it can be
+/// removed by.
+pub async fn add_name_denied(conn: &Pool, guild: u64, pattern: &str) -> ZweiDbRes<i64> {
+    let g = guild as i64;
+    let patternid = query!(
+        "INSERT INTO name_denylist (serverid, pattern) VALUES (?, ?)",
+        g,
+        pattern
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to deny a name pattern for guild ID {guild}\n\t{e}");
+        e
+    })?
+    .last_insert_rowid();
+    trace!("Denied name pattern {patternid} for guild ID {guild}");
+    Ok(patternid)
+}
+
+/// # remove_name_denied
+/// Allows names matching a denied pattern again. Returns 0 if it didn't exist.
+pub async fn remove_name_denied(conn: &Pool, guild: u64, patternid: i64) -> ZweiDbRes<u64> {
+    let g = guild as i64;
+    rowcount!(
+        query!(
+            "DELETE FROM name_denylist WHERE serverid = ? AND patternid = ?",
+            g,
+            patternid
+        )
+        .execute(conn),
+        "Removing denied name pattern {} for guild ID {}",
+        "Failed to remove denied name pattern {} for guild ID {}",
+        patternid,
+        guild
+    )
+}
+
+/// # Appeal
+/// A ban appeal as stored in the `appeals` table.
+pub struct Appeal {
+    pub appealid: i64,
+    pub serverid: i64,
+    pub userid: i64,
+    pub reason: String,
+    pub message: String,
+    pub status: String,
+    pub submitted: i64,
+    pub moderator: i64,
+}
+
+/// # add_appeal
+/// Opens a pending appeal for a ban, returning the ID the user can submit it
+/// with.
+pub async fn add_appeal(conn: &Pool, guild: u64, user: u64, reason: &str) -> ZweiDbRes<i64> {
+    let g = guild as i64;
+    let u = user as i64;
+    let appealid = query!(
+        "INSERT INTO appeals (serverid, userid, reason) VALUES (?, ?, ?)",
+        g,
+        u,
+        reason
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        error!("Failed to open an appeal for user ID {user} in guild ID {guild}\n\t{e}");
+        e
+    })?
+    .last_insert_rowid();
+    trace!("Opened appeal {appealid} for user ID {user} in guild ID {guild}");
+    Ok(appealid)
+}
+
+/// # remove_appeal
+/// Deletes an appeal, for when the ban it was opened for didn't go through.
+pub async fn remove_appeal(conn: &Pool, appealid: i64) -> ZweiDbRes<u64> {
+    rowcount!(
+        query!("DELETE FROM appeals WHERE appealid = ?", appealid).execute(conn),
+        "Removing appeal {}",
+        "Failed to remove appeal {}",
+        appealid
+    )
+}
+
+/// # get_appeal
+/// Fetches an appeal by its ID.
+pub async fn get_appeal(conn: &Pool, appealid: i64) -> ZweiDbRes<Option<Appeal>> {
+    query_as!(
+        Appeal,
+        "SELECT appealid, serverid, userid, reason, message, status, submitted, moderator FROM appeals WHERE appealid = ?",
+        appealid
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// # last_appeal
+/// Gets when this user last submitted an appeal to this guild, or 0 if they
+/// never did. Used to keep people from flooding staff with appeals.
+pub async fn last_appeal(conn: &Pool, guild: u64, user: u64) -> ZweiDbRes<i64> {
+    let g = guild as i64;
+    let u = user as i64;
+    query!(
+        r#"SELECT MAX(submitted) AS "last: i64" FROM appeals WHERE serverid = ? AND userid = ?"#,
+        g,
+        u
+    )
+    .fetch_one(conn)
+    .await
+    .map(|row| row.last.unwrap_or(0))
+}
+
+/// # submit_appeal
+/// Stores the user's message on a pending appeal and opens it for staff.
+/// Returns 0 if the appeal wasn't pending anymore.
+pub async fn submit_appeal(conn: &Pool, appealid: i64, message: &str) -> ZweiDbRes<u64> {
+    rowcount!(
+        query!(
+            "UPDATE appeals SET message = ?, status = 'open', submitted = strftime('%s', 'now') WHERE appealid = ? AND status = 'pending'",
+            message,
+            appealid
+        )
+        .execute(conn),
+        "Submitting appeal {}",
+        "Failed to submit appeal {}",
+        appealid
+    )
+}
+
+/// # unsubmit_appeal
+/// Puts a submitted appeal back to pending, for when it couldn't be passed on
+/// to staff. This also lifts the cooldown the submission started.
+pub async fn unsubmit_appeal(conn: &Pool, appealid: i64) -> ZweiDbRes<u64> {
+    rowcount!(
+        query!(
+            "UPDATE appeals SET message = '', status = 'pending', submitted = 0 WHERE appealid = ? AND status = 'open'",
+            appealid
+        )
+        .execute(conn),
+        "Putting appeal {} back to pending",
+        "Failed to put appeal {} back to pending",
+        appealid
+    )
+}
+
+/// # set_appeal_logmsg
+/// Remembers which message in the appeals channel shows this appeal.
+pub async fn set_appeal_logmsg(conn: &Pool, appealid: i64, logmsg: u64) -> ZweiDbRes<u64> {
+    let l = logmsg as i64;
+    rowcount!(
+        query!(
+            "UPDATE appeals SET logmsg = ? WHERE appealid = ?",
+            l,
+            appealid
+        )
+        .execute(conn),
+        "Storing the message for appeal {}",
+        "Failed to store the message for appeal {}",
+        appealid
+    )
+}
+
+/// # decide_appeal
+/// Marks an open appeal as `accepted` or `denied` by a moderator. Returns 0 if
+/// it wasn't open anymore, so two moderators can't both act on it.
+pub async fn decide_appeal(
+    conn: &Pool,
+    appealid: i64,
+    status: &str,
+    moderator: u64,
+) -> ZweiDbRes<u64> {
+    let m = moderator as i64;
+    rowcount!(
+        query!(
+            "UPDATE appeals SET status = ?, moderator = ? WHERE appealid = ? AND status = 'open'",
+            status,
+            m,
+            appealid
+        )
+        .execute(conn),
+        "Marking appeal {} as {}",
+        "Failed to mark appeal {} as {}",
+        appealid,
+        status
+    )
+}
@@ -0,0 +1,163 @@
+use serenity::{
+    framework::standard::CommandResult, http::StatusCode, model::prelude::*, prelude::*, Error,
+};
+
+use crate::{send_err, send_err_titled};
+
+/// # ModerationDenied
+/// The reasons a moderation action on a member can be refused before it's
+/// even sent to Discord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModerationDenied {
+    /// No user mention or ID was given.
+    NoTarget,
+    /// The target owns the server, nobody can touch them.
+    Owner,
+    /// The target is me.
+    Myself,
+    /// The invoker is trying to act on themselves.
+    Yourself,
+    /// The target's highest role is equal to or above mine.
+    AboveMe,
+    /// The target's highest role is equal to or above the invoker's.
+    AboveYou,
+    /// Discord couldn't tell me what I needed to know to check the others.
+    Unverified,
+}
+
+impl ModerationDenied {
+    /// # explain
+    /// Explains the refusal to the invoker. `verb` completes "I can't ...",
+    /// like `kick` or `ban`.
+    pub fn explain(&self, verb: &str) -> String {
+        match self {
+            Self::NoTarget => format!("Please give me a user mention or an ID to {verb}."),
+            Self::Owner => format!("I can't {verb} the owner of their own server."),
+            Self::Myself => format!("I'm not going to {verb} myself."),
+            Self::Yourself => format!("You can't {verb} yourself."),
+            Self::AboveMe => {
+                format!("I can't {verb} someone whose roles are equal to or higher than my own!")
+            }
+            Self::AboveYou => {
+                format!(
+                    "You can't {verb} someone whose roles are equal to or higher than your own!"
+                )
+            }
+            Self::Unverified => {
+                format!("I couldn't check whether you may {verb} them, please try again later.")
+            }
+        }
+    }
+}
+
+/// # highest_position
+/// The position of a member's highest role. Members without any roles only
+/// have `@everyone`, which always sits at the bottom.
+fn highest_position(ctx: &Context, member: &Member) -> i64 {
+    member
+        .highest_role_info(ctx)
+        .map(|(_, pos)| pos)
+        .unwrap_or(0)
+}
+
+/// # check
+/// Checks whether a moderation action on `target` may go ahead. The owner and
+/// I can never be targeted, and my highest role has to be above the target's.
+/// When `invoker` is given, their highest role has to be above the target's as
+/// well, unless they own the server. Automatic actions pass `None`.
+///
+/// Users that aren't in the server don't have any roles to compare, so only
+/// the owner and self checks apply to them. Any other failure to look things
+/// up refuses the action, as it can't be told apart from being allowed.
+pub async fn check(
+    ctx: &Context,
+    guild: GuildId,
+    invoker: Option<UserId>,
+    target: UserId,
+) -> Result<(), ModerationDenied> {
+    if target.0 == 0 {
+        return Err(ModerationDenied::NoTarget);
+    }
+    let me = ctx.cache.current_user_id();
+    if target == me {
+        return Err(ModerationDenied::Myself);
+    }
+    if invoker == Some(target) {
+        return Err(ModerationDenied::Yourself);
+    }
+    let owner = match guild.to_guild_cached(ctx) {
+        Some(g) => g.owner_id,
+        None => match guild.to_partial_guild(ctx).await {
+            Ok(g) => g.owner_id,
+            Err(e) => {
+                log::warn!("Couldn't look up the owner of {}\n\t{e}", guild.0);
+                return Err(ModerationDenied::Unverified);
+            }
+        },
+    };
+    if target == owner {
+        return Err(ModerationDenied::Owner);
+    }
+    let target_pos = match guild.member(ctx, target).await {
+        Ok(member) => highest_position(ctx, &member),
+        Err(Error::Http(e)) if e.status_code() == Some(StatusCode::NOT_FOUND) => return Ok(()),
+        Err(e) => {
+            log::warn!("Couldn't look up member {} of {}\n\t{e}", target.0, guild.0);
+            return Err(ModerationDenied::Unverified);
+        }
+    };
+    match guild.member(ctx, me).await {
+        Ok(member) if highest_position(ctx, &member) <= target_pos => {
+            return Err(ModerationDenied::AboveMe);
+        }
+        Ok(_) => (),
+        Err(e) => {
+            log::warn!("Couldn't look up myself in {}\n\t{e}", guild.0);
+            return Err(ModerationDenied::Unverified);
+        }
+    }
+    match invoker {
+        Some(id) if id != owner => match guild.member(ctx, id).await {
+            Ok(member) if highest_position(ctx, &member) > target_pos => Ok(()),
+            Ok(_) => Err(ModerationDenied::AboveYou),
+            Err(e) => {
+                log::warn!("Couldn't look up member {} of {}\n\t{e}", id.0, guild.0);
+                Err(ModerationDenied::Unverified)
+            }
+        },
+        _ => Ok(()),
+    }
+}
+
+/// # guard
+/// Runs [`check`] for a command and lets the invoker know if the action was
+/// refused. Returns whether the command may go ahead. Every moderation command
+/// acting on a member should go through here first.
+pub async fn guard(
+    ctx: &Context,
+    msg: &Message,
+    target: UserId,
+    verb: &str,
+) -> CommandResult<bool> {
+    let guild = msg.guild_id.unwrap_or_default();
+    match check(ctx, guild, Some(msg.author.id), target).await {
+        Ok(()) => Ok(true),
+        Err(ModerationDenied::Myself) => {
+            msg.reply_ping(ctx, "<:ZweiAngery:844167326243880960>")
+                .await?;
+            Ok(false)
+        }
+        Err(why @ ModerationDenied::NoTarget) => {
+            send_err_titled(ctx, msg, "No target provided!", why.explain(verb)).await?;
+            Ok(false)
+        }
+        Err(why @ ModerationDenied::Owner) => {
+            send_err_titled(ctx, msg, "That's not possible!", why.explain(verb)).await?;
+            Ok(false)
+        }
+        Err(why) => {
+            send_err(ctx, msg, why.explain(verb)).await?;
+            Ok(false)
+        }
+    }
+}
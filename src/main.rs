@@ -5,7 +5,15 @@ use serenity::{
     framework,
     framework::standard::{macros::help, Args, CommandGroup, CommandResult, HelpOptions},
     http::Http,
-    model::{channel::Message, event::ResumedEvent, gateway::Ready, id::UserId},
+    model::{
+        application::component::ButtonStyle,
+        application::interaction::{Interaction, InteractionResponseType},
+        channel::Message,
+        event::ResumedEvent,
+        gateway::Ready,
+        guild::Member,
+        id::UserId,
+    },
     prelude::*, // also implies tokio Mutex
     utils::Color,
     Result as SerenityResult,
@@ -13,13 +21,22 @@ use serenity::{
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 
 extern crate log;
-use env_logger;
 
+mod antiraid;
+mod antispam;
+mod appeals;
+mod automod;
 mod commands;
 mod dbx;
+mod guard;
+mod modlog;
+mod names;
+mod scheduler;
+mod tagpanel;
 mod zwei_conf;
 
 /// # ShardManagerContainer
@@ -54,6 +71,7 @@ impl TypeMapKey for ZweiOwners {
 }
 
 use dbx::ZweiDbConn;
+use scheduler::ZweiScheduler;
 
 /// # Handler
 /// The Zwei implementation for `serenity::client::EventHandler`.
@@ -63,17 +81,83 @@ impl EventHandler for Handler {
     /// # ready
     /// Function called when the `serenity::client` fires its `ready` event.
     /// This gets called whenever the bot is being operated, and is used to set
-    /// some initial state like the startup time of the bot.
+    /// some initial state like the startup time of the bot. It also hands all
+    /// pending actions in the database to the scheduler, so nothing is lost
+    /// when Zwei restarts.
     async fn ready(&self, ctx: Context, ready: Ready) {
         let time = Utc::now();
-        let mut data = ctx.data.write().await;
-        data.get_mut::<ZweiData>()
-            .expect("Couldn't get lifetime info...")
-            .entry(String::from("Init"))
-            .or_insert(time.timestamp());
+        {
+            let mut data = ctx.data.write().await;
+            data.get_mut::<ZweiData>()
+                .expect("Couldn't get lifetime info...")
+                .entry(String::from("Init"))
+                .or_insert(time.timestamp());
+        }
+        {
+            let data = ctx.data.read().await;
+            if let (Some(conn), Some(tx)) = (data.get::<ZweiDbConn>(), data.get::<ZweiScheduler>())
+            {
+                match dbx::get_scheduled(conn).await {
+                    Ok(actions) => {
+                        log::info!("Reloading {} scheduled actions", actions.len());
+                        actions.into_iter().for_each(|a| {
+                            let _ = tx.send(a);
+                        });
+                    }
+                    Err(e) => log::error!("Couldn't reload scheduled actions!\n\t{e}"),
+                }
+            }
+        }
+        // Tags may have changed while we were away.
+        tagpanel::refresh_panels(&ctx, None).await;
         println!("{} connected to Discord at {}", ready.user.name, time)
     }
 
+    /// # guild_member_addition
+    /// Fired whenever someone joins a guild. Runs the anti-raid checks first,
+    /// then makes sure people that left while muted don't come back unmuted,
+    /// and that their name follows the rules.
+    async fn guild_member_addition(&self, ctx: Context, member: Member) {
+        antiraid::on_join(&ctx, &member).await;
+        commands::modtools::reapply_mute(&ctx, &member).await;
+        names::enforce_name(&ctx, &member).await;
+    }
+
+    /// # guild_member_update
+    /// Fired whenever a member changes, like their roles or nickname. Only
+    /// name changes matter here, those are checked against the name rules.
+    async fn guild_member_update(&self, ctx: Context, old: Option<Member>, new: Member) {
+        if old.is_some_and(|old| old.display_name() == new.display_name()) {
+            return;
+        }
+        names::enforce_name(&ctx, &new).await;
+    }
+
+    /// # message
+    /// Fired for every message Zwei can see. Commands are dispatched by the
+    /// framework on their own, this is only used for automatic moderation.
+    /// Messages automod already acted on aren't counted by anti-spam.
+    async fn message(&self, ctx: Context, msg: Message) {
+        if !automod::check_message(&ctx, &msg).await {
+            antispam::check_message(&ctx, &msg).await;
+        }
+    }
+
+    /// # interaction_create
+    /// Fired when someone uses a button or menu. Components that aren't
+    /// waited on by a collector, like the ones on appeals and tag panels, are
+    /// dispatched from here by the prefix of their custom ID, so they keep
+    /// working after a restart.
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::MessageComponent(press) = interaction {
+            match press.data.custom_id.split(':').next() {
+                Some("appeal") => appeals::on_button(&ctx, &press).await,
+                Some("tagpanel") => tagpanel::on_select(&ctx, &press).await,
+                _ => (),
+            }
+        }
+    }
+
     /// # resume
     /// This is run whenever something caused a (percieved) interruption in the
     /// connection to Discord, causing the active session to resume.
@@ -89,10 +173,10 @@ impl EventHandler for Handler {
 pub async fn get_name(msg: &Message, ctx: &Context, mem: UserId) -> SerenityResult<String> {
     if let Some(g) = msg.guild(ctx) {
         let gmem = g.member(ctx, mem).await?;
-        return Ok(gmem.display_name().into_owned());
+        Ok(gmem.display_name().into_owned())
     } else {
         let user = mem.to_user(ctx).await?;
-        return Ok(format!("{:}#{:}", user.name, user.discriminator));
+        Ok(format!("{:}#{:}", user.name, user.discriminator))
     }
 }
 
@@ -118,6 +202,7 @@ pub async fn get_prefix(msg: &Message, ctx: &Context) -> String {
         .unwrap_or(String::from(";"))
 }
 
+#[allow(clippy::result_large_err)]
 pub fn get_color(color: &str) -> SerenityResult<Color> {
     if let Ok(col) = u32::from_str_radix(color, 16) {
         Ok(Color::from(col))
@@ -180,6 +265,102 @@ pub fn sanitize_txt(txt: &str) -> String {
     sanitized
 }
 
+/// # fit_embed
+/// Function to join lines into an embed description. Embed descriptions cap
+/// out at 4096 characters, so this keeps the last lines that fit and mentions
+/// how many of the earlier ones were left out. A newest line that doesn't fit
+/// on its own is cut short. Meant for listings that run oldest to newest, like
+/// warnings.
+pub fn fit_embed(lines: &[String]) -> String {
+    fit_lines(lines, 4000)
+}
+
+/// # fit_lines
+/// Does the same as [`fit_embed`] within `budget` characters, for descriptions
+/// that put some text of their own before the lines.
+pub fn fit_lines(lines: &[String], budget: usize) -> String {
+    let mut charcount: usize = 0;
+    let kept = lines
+        .iter()
+        .rev()
+        .take_while(|line| {
+            charcount += line.chars().count() + 1;
+            charcount <= budget
+        })
+        .count();
+    let mut hidden = lines.len() - kept;
+    let mut shown = lines[hidden..].join("\n");
+    if kept == 0 && hidden > 0 {
+        hidden -= 1;
+        let cut: String = lines[hidden]
+            .chars()
+            .take(budget.saturating_sub(3))
+            .collect();
+        shown = cut + "...";
+    }
+    match hidden {
+        0 => shown,
+        1 => format!("_...1 older entry left out._\n{shown}"),
+        _ => format!("_...{hidden} older entries left out._\n{shown}"),
+    }
+}
+
+/// # parse_duration
+/// Function to parse human-friendly durations like `90s`, `30m`, `1h30m` or
+/// `7d` into an amount of seconds. Understands seconds, minutes, hours, days
+/// and weeks. Every number needs a unit, so bare numbers and garbage input
+/// both result in `None`, as do durations of zero.
+pub fn parse_duration(txt: &str) -> Option<u64> {
+    let mut total: u64 = 0;
+    let mut num: Option<u64> = None;
+    for c in txt.trim().to_lowercase().chars() {
+        if let Some(d) = c.to_digit(10) {
+            num = Some(num.unwrap_or(0).checked_mul(10)?.checked_add(d as u64)?);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            _ => return None,
+        };
+        total = total.checked_add(num.take()?.checked_mul(unit)?)?;
+    }
+    match (num, total) {
+        (None, 1..) => Some(total),
+        _ => None,
+    }
+}
+
+/// # fmt_duration
+/// Function to turn an amount of seconds into something readable, like
+/// `1 day, 2 hours and 1 second`. Units that would be zero are left out.
+pub fn fmt_duration(secs: u64) -> String {
+    let units = [
+        ("week", 604800),
+        ("day", 86400),
+        ("hour", 3600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+    let mut left = secs;
+    let mut parts: Vec<String> = Vec::with_capacity(units.len());
+    for (name, size) in units {
+        let n = left / size;
+        left %= size;
+        if n > 0 {
+            parts.push(format!("{n} {name}{}", if n != 1 { "s" } else { "" }));
+        }
+    }
+    match parts.pop() {
+        None => "0 seconds".to_owned(),
+        Some(last) if parts.is_empty() => last,
+        Some(last) => format!("{} and {last}", parts.join(", ")),
+    }
+}
+
 /// # send_err
 /// Central function to send an embed indicating something went wrong.
 /// This is the version that's not too descriptive.
@@ -189,7 +370,7 @@ pub fn sanitize_txt(txt: &str) -> String {
 /// * `ctx` - Command context
 /// * `msg` - The message that invoked the command leading to the error.
 /// * `errtxt` - Anything implementing `std::fmt::Display` as textual
-///              indication of what went wrong.
+///   indication of what went wrong.
 pub async fn send_err(
     ctx: &Context,
     msg: &Message,
@@ -208,9 +389,9 @@ pub async fn send_err(
 /// * `ctx` - Command context
 /// * `msg` - The message that invoked the command leading to the error.
 /// * `title` - Anything that implements [`std::fmt::Display`], like the
-///             command name or some other descriptive heading.
+///   command name or some other descriptive heading.
 /// * `errtxt` - Anything implementing [`std::fmt::Display`] as textual
-///              indication of what went wrong.
+///   indication of what went wrong.
 pub async fn send_err_titled(
     ctx: &Context,
     msg: &Message,
@@ -226,6 +407,34 @@ pub async fn send_err_titled(
     Ok(())
 }
 
+/// # db_conn
+/// Central function to get a handle on the database for a command. If there's
+/// no connection to be had, this logs it and lets the invoker know, and the
+/// command should return the `Err` value as is.
+///
+/// # Arguments
+///
+/// * `ctx` - Command context
+/// * `msg` - The message that invoked the command needing the database.
+/// * `what` - What the database is needed for, completing "Failed to acquire
+///   database connection object to" in the log.
+pub async fn db_conn(ctx: &Context, msg: &Message, what: &str) -> Result<dbx::Pool, CommandResult> {
+    let conn = ctx.data.read().await.get::<ZweiDbConn>().cloned();
+    match conn {
+        Some(conn) => Ok(conn),
+        None => {
+            log::error!("Failed to acquire database connection object to {what}!");
+            Err(send_err_titled(
+                ctx,
+                msg,
+                "Catastrophic failure",
+                "Could not acquire the database connection object.\nContact support if this keeps happening!",
+            )
+            .await)
+        }
+    }
+}
+
 /// # send_ok
 /// Function to indicate successful processing of a command. Uses Zwei's
 /// pretty colors to indicate everything is good.
@@ -235,9 +444,9 @@ pub async fn send_err_titled(
 /// * `ctx` - Command context
 /// * `msg` - The message that invoked the command that was completed.
 /// * `title` - Anything that implements [`std::fmt::Display`], like the
-///             command name or some other descriptive heading.
+///   command name or some other descriptive heading.
 /// * `msgtxt` - Anything implementing [`std::fmt::Display`] as textual message
-///              returning information about command execution to the user.
+///   returning information about command execution to the user.
 pub async fn send_ok(
     ctx: &Context,
     msg: &Message,
@@ -253,6 +462,156 @@ pub async fn send_ok(
     Ok(())
 }
 
+/// # confirm
+/// Asks the invoker to confirm an action with a pair of buttons. Only the
+/// invoker can answer, and not answering within 30 seconds counts as a no.
+/// The buttons are removed afterwards, leaving a note of what was chosen.
+///
+/// # Arguments
+///
+/// * `ctx` - Command context
+/// * `msg` - The message that invoked the command asking for confirmation.
+/// * `title` - Anything that implements [`std::fmt::Display`], like the
+///   command name or some other descriptive heading.
+/// * `question` - Anything implementing [`std::fmt::Display`] describing what
+///   is about to happen.
+pub async fn confirm(
+    ctx: &Context,
+    msg: &Message,
+    title: impl std::fmt::Display,
+    question: impl std::fmt::Display,
+) -> CommandResult<bool> {
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    let (title, question) = (title.to_string(), question.to_string());
+    let mut prompt = msg
+        .channel_id
+        .send_message(ctx, |mes| {
+            mes.embed(|e| e.color(color).title(&title).description(&question))
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id("confirm")
+                                .label("Confirm")
+                                .style(ButtonStyle::Danger)
+                        })
+                        .create_button(|b| {
+                            b.custom_id("cancel")
+                                .label("Cancel")
+                                .style(ButtonStyle::Secondary)
+                        })
+                    })
+                })
+        })
+        .await?;
+    let answer = prompt
+        .await_component_interaction(ctx)
+        .author_id(msg.author.id)
+        .timeout(Duration::from_secs(30))
+        .await;
+    let (confirmed, note) = match &answer {
+        Some(pressed) => {
+            let _ = pressed
+                .create_interaction_response(ctx, |r| {
+                    r.kind(InteractionResponseType::DeferredUpdateMessage)
+                })
+                .await;
+            match pressed.data.custom_id.as_str() {
+                "confirm" => (true, "Confirmed."),
+                _ => (false, "Cancelled, nothing was done."),
+            }
+        }
+        None => (false, "Nobody answered, so nothing was done."),
+    };
+    prompt
+        .edit(ctx, |mes| {
+            mes.embed(|e| {
+                e.color(color)
+                    .title(&title)
+                    .description(format!("{question}\n\n_{note}_"))
+            })
+            .components(|c| c)
+        })
+        .await?;
+    Ok(confirmed)
+}
+
+/// # paginate
+/// Sends pages of text as a single embed with buttons to flip through them.
+/// Only the invoker can flip pages, and the buttons are removed after two
+/// minutes without anyone using them. A single page is sent as is.
+///
+/// # Arguments
+///
+/// * `ctx` - Command context
+/// * `msg` - The message that invoked the command.
+/// * `title` - Anything that implements [`std::fmt::Display`], shown above
+///   every page.
+/// * `pages` - The text of each page, which should fit in an embed.
+pub async fn paginate(
+    ctx: &Context,
+    msg: &Message,
+    title: impl std::fmt::Display,
+    pages: &[String],
+) -> CommandResult {
+    if pages.len() < 2 {
+        return send_ok(ctx, msg, title, pages.first().map_or("", |p| p.as_str())).await;
+    }
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    let title = title.to_string();
+    let mut current: usize = 0;
+    let mut shown = msg
+        .channel_id
+        .send_message(ctx, |mes| {
+            mes.embed(|e| {
+                e.color(color)
+                    .title(&title)
+                    .description(&pages[current])
+                    .footer(|f| f.text(format!("Page 1/{}", pages.len())))
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.custom_id("previous")
+                            .label("Previous")
+                            .style(ButtonStyle::Secondary)
+                    })
+                    .create_button(|b| {
+                        b.custom_id("next")
+                            .label("Next")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await?;
+    while let Some(pressed) = shown
+        .await_component_interaction(ctx)
+        .author_id(msg.author.id)
+        .timeout(Duration::from_secs(120))
+        .await
+    {
+        current = match pressed.data.custom_id.as_str() {
+            "next" => (current + 1) % pages.len(),
+            _ => (current + pages.len() - 1) % pages.len(),
+        };
+        pressed
+            .create_interaction_response(ctx, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.embed(|e| {
+                            e.color(color)
+                                .title(&title)
+                                .description(&pages[current])
+                                .footer(|f| f.text(format!("Page {}/{}", current + 1, pages.len())))
+                        })
+                    })
+            })
+            .await?;
+    }
+    shown.edit(ctx, |mes| mes.components(|c| c)).await?;
+    Ok(())
+}
+
 /// # zwei_help
 /// The help command as provided by Serenity, configured for Zwei.
 /// This is what is called when someone uses `;help` with or without arguments.
@@ -339,6 +698,15 @@ async fn main() {
         .help(&ZWEI_HELP)
         // Register normal command groups
         .group(&commands::modtools::MODTOOLS_GROUP)
+        .group(&commands::modtools::ESCALATION_GROUP)
+        .group(&commands::cases::CASES_GROUP)
+        .group(&commands::appeals::APPEALS_GROUP)
+        .group(&commands::antiraid::ANTIRAID_GROUP)
+        .group(&commands::automod::AUTOMOD_GROUP)
+        .group(&commands::antispam::ANTISPAM_GROUP)
+        .group(&commands::names::NAMES_GROUP)
+        .group(&commands::notes::NOTES_GROUP)
+        .group(&commands::info::INFO_GROUP)
         .group(&commands::misc::MISC_GROUP)
         .group(&commands::misc::PREFIX_GROUP)
         .group(&commands::subs::TAG_GROUP);
@@ -369,6 +737,20 @@ async fn main() {
         // Can't run without a DB.
         .expect("Could not find a database to mangle!");
 
+    // Bring the schema up to date, databases from older versions included.
+    sqlx::migrate!()
+        .run(&dbpool)
+        .await
+        .expect("Could not bring the database up to date");
+
+    // The scheduler only needs HTTP access and its own handle on the DB
+    let (schedule_tx, schedule_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(scheduler::run(
+        bot.cache_and_http.http.clone(),
+        dbpool.clone(),
+        schedule_rx,
+    ));
+
     // Scope this so the lock is released at the end
     {
         // grab bot data for writing, insert a cloned `Arc<>` for global access
@@ -382,8 +764,9 @@ async fn main() {
         // Add owner list and all known prefixes for access
         data.insert::<ZweiOwners>(owners.clone());
         data.insert::<ZweiPrefixes>(dbx::get_all_prefixes(&dbpool).await);
-        // Store the connection pool
+        // Store the connection pool and the scheduler queue
         data.insert::<ZweiDbConn>(dbpool);
+        data.insert::<ZweiScheduler>(schedule_tx);
     }
     // Grab another copy of the `Arc<>` in order to allow shutting down cleanly
     let shard_manager = bot.shard_manager.clone();
@@ -407,3 +790,106 @@ async fn main() {
         .close()
         .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("90s"), Some(90));
+        assert_eq!(parse_duration("30m"), Some(1800));
+        assert_eq!(parse_duration("1h30m"), Some(5400));
+        assert_eq!(parse_duration("7d"), Some(604800));
+        assert_eq!(parse_duration("2W"), Some(1209600));
+        assert_eq!(parse_duration(" 1d12h "), Some(129600));
+    }
+
+    #[test]
+    fn parse_duration_rejects_garbage() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("   "), None);
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("1h30"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_duration("1h 30m"), None);
+        assert_eq!(parse_duration("-5m"), None);
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("0h0m"), None);
+    }
+
+    #[test]
+    fn parse_duration_overflow() {
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+        assert_eq!(parse_duration("40000000000000w"), None);
+        assert_eq!(parse_duration("18446744073709551615s1s"), None);
+        assert_eq!(
+            parse_duration("18446744073709551615s"),
+            Some(18446744073709551615)
+        );
+    }
+
+    #[test]
+    fn fmt_duration_parts() {
+        assert_eq!(fmt_duration(0), "0 seconds");
+        assert_eq!(fmt_duration(1), "1 second");
+        assert_eq!(fmt_duration(60), "1 minute");
+        assert_eq!(fmt_duration(3601), "1 hour and 1 second");
+        assert_eq!(fmt_duration(90061), "1 day, 1 hour, 1 minute and 1 second");
+        assert_eq!(fmt_duration(1209600), "2 weeks");
+    }
+
+    #[test]
+    fn fmt_duration_reads_parsed_durations() {
+        let read = |txt| fmt_duration(parse_duration(txt).unwrap());
+        assert_eq!(read("1h30m"), "1 hour and 30 minutes");
+        assert_eq!(read("1w2d"), "1 week and 2 days");
+        assert_eq!(read("120s"), "2 minutes");
+        assert!(fmt_duration(u64::MAX).ends_with("and 15 seconds"));
+    }
+
+    #[test]
+    fn fit_embed_empty() {
+        assert_eq!(fit_embed(&[]), "");
+    }
+
+    #[test]
+    fn fit_embed_keeps_everything_that_fits() {
+        // Every line counts one extra for its newline, 4 * 1000 is the limit.
+        let lines = vec!["x".repeat(999); 4];
+        assert_eq!(fit_embed(&lines), lines.join("\n"));
+    }
+
+    #[test]
+    fn fit_embed_drops_oldest_lines() {
+        let mut lines = vec!["old".to_owned()];
+        lines.extend(vec!["x".repeat(999); 4]);
+        let fitted = fit_embed(&lines);
+        assert!(fitted.starts_with("_...1 older entry left out._\n"));
+        assert!(!fitted.contains("old\n"));
+        assert_eq!(fitted.matches(&"x".repeat(999)).count(), 4);
+    }
+
+    #[test]
+    fn fit_embed_cuts_a_single_huge_line() {
+        let mut lines = vec!["x".repeat(5000)];
+        let fitted = fit_embed(&lines);
+        assert_eq!(fitted.chars().count(), 4000);
+        assert!(fitted.starts_with("xxx") && fitted.ends_with("x..."));
+        lines.splice(0..0, ["old".to_owned(), "older".to_owned()]);
+        let fitted = fit_embed(&lines);
+        assert!(fitted.starts_with("_...2 older entries left out._\nxxx"));
+        assert!(fitted.ends_with("x..."));
+    }
+
+    #[test]
+    fn fit_lines_keeps_to_the_budget() {
+        let lines = vec!["x".repeat(99); 3];
+        assert_eq!(fit_lines(&lines, 300), lines.join("\n"));
+        let fitted = fit_lines(&lines, 299);
+        assert!(fitted.starts_with("_...1 older entry left out._\n"));
+        assert_eq!(fitted.matches(&"x".repeat(99)).count(), 2);
+        assert_eq!(fit_lines(&lines[..1], 50).chars().count(), 50);
+    }
+}
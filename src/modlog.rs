@@ -0,0 +1,185 @@
+use serenity::{builder::CreateEmbed, model::prelude::*, prelude::*, utils::Color};
+
+use crate::{
+    dbx::{self, Case, ZweiDbConn},
+    get_color, zwei_conf,
+};
+
+/// # log_case
+/// Opens a numbered case for a moderation action and posts it to the guild's
+/// mod-log channel, if one is set. Returns the case number, or `None` if the
+/// case couldn't be stored. Failures are only logged, since the action itself
+/// has already happened by the time it's written down.
+///
+/// # Arguments
+///
+/// * `ctx` - The context to reach the database and Discord through.
+/// * `guild` - The guild the action was taken in.
+/// * `moderator` - Whoever took the action. This is me for automatic actions.
+/// * `target` - The ID of the user acted upon, or the channel for purges.
+/// * `action` - A short name for the action, like `kick` or `ban`.
+/// * `reason` - The reason given for the action.
+pub async fn log_case(
+    ctx: &Context,
+    guild: GuildId,
+    moderator: UserId,
+    target: u64,
+    action: &str,
+    reason: &str,
+) -> Option<i64> {
+    let (case, modlog) = {
+        let botdata = ctx.data.read().await;
+        let conn = botdata.get::<ZweiDbConn>()?;
+        let case = dbx::add_case(conn, guild.0, moderator.0, target, action, reason)
+            .await
+            .ok()?;
+        (case, dbx::get_modlog(conn, guild.0).await.unwrap_or(0))
+    };
+    if modlog != 0 {
+        post_case(ctx, guild, ChannelId(modlog), &case).await;
+    }
+    Some(case.caseno)
+}
+
+/// # post_case
+/// Posts a case to the mod-log channel and remembers the message for it.
+async fn post_case(ctx: &Context, guild: GuildId, channel: ChannelId, case: &Case) {
+    let color = match get_color(&zwei_conf::CONF.ok_color) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    match channel
+        .send_message(ctx, |m| m.embed(|e| case_embed(e, case, color)))
+        .await
+    {
+        Ok(logmsg) => {
+            let botdata = ctx.data.read().await;
+            if let Some(conn) = botdata.get::<ZweiDbConn>() {
+                let _ = dbx::set_case_logmsg(conn, guild.0, case.caseno, logmsg.id.0).await;
+            }
+        }
+        Err(e) => log::warn!(
+            "Couldn't post case {} to the mod-log of guild {}\n\t{e}",
+            case.caseno,
+            guild.0
+        ),
+    }
+}
+
+/// # refresh_case
+/// Updates the mod-log message for a case after it was amended. Cases that
+/// were never posted, or whose message is gone, are left alone.
+pub async fn refresh_case(ctx: &Context, guild: GuildId, case: &Case) {
+    let modlog = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => dbx::get_modlog(conn, guild.0).await.unwrap_or(0),
+            None => 0,
+        }
+    };
+    if modlog == 0 || case.logmsg == 0 {
+        return;
+    }
+    let color = match get_color(&zwei_conf::CONF.ok_color) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    if let Err(e) = ChannelId(modlog)
+        .edit_message(ctx, case.logmsg as u64, |m| {
+            m.embed(|e| case_embed(e, case, color))
+        })
+        .await
+    {
+        log::warn!(
+            "Couldn't update case {} in the mod-log of guild {}\n\t{e}",
+            case.caseno,
+            guild.0
+        );
+    }
+}
+
+/// # alert
+/// Posts a warning to the guild's mod-log channel that isn't tied to a case,
+/// like a raid being detected. Does nothing if no mod-log channel is set.
+pub async fn alert(ctx: &Context, guild: GuildId, title: &str, text: &str) {
+    let modlog = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => dbx::get_modlog(conn, guild.0).await.unwrap_or(0),
+            None => 0,
+        }
+    };
+    if modlog == 0 {
+        return;
+    }
+    let color = match get_color(&zwei_conf::CONF.err_color) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    if let Err(e) = ChannelId(modlog)
+        .send_message(ctx, |m| {
+            m.embed(|e| e.color(color).title(title).description(text))
+        })
+        .await
+    {
+        log::warn!(
+            "Couldn't post an alert to the mod-log of guild {}\n\t{e}",
+            guild.0
+        );
+    }
+}
+
+/// Embed fields can't hold more characters than this.
+const FIELD_MAX: usize = 1024;
+
+/// # reason_field
+/// Fits a case reason into an embed field, cutting off anything past what a
+/// field can hold so long reasons don't get the whole embed rejected.
+fn reason_field(reason: &str) -> String {
+    match reason.chars().count() {
+        0 => "_No reason given._".to_owned(),
+        n if n > FIELD_MAX => {
+            let cut: String = reason.chars().take(FIELD_MAX - 1).collect();
+            format!("{cut}…")
+        }
+        _ => reason.to_owned(),
+    }
+}
+
+/// # case_embed
+/// Fills in an embed describing a case, for both the mod-log and `case`.
+pub fn case_embed<'a>(e: &'a mut CreateEmbed, case: &Case, color: Color) -> &'a mut CreateEmbed {
+    e.color(color)
+        .title(format!(
+            "Case #{} | {}",
+            case.caseno,
+            action_name(&case.action)
+        ))
+        .field("Target", target_mention(case), true)
+        .field("Moderator", format!("<@{}>", case.moderator), true)
+        .field("Reason", reason_field(&case.reason), false);
+    if let Ok(ts) = Timestamp::from_unix_timestamp(case.created) {
+        e.timestamp(ts);
+    }
+    e
+}
+
+/// # action_name
+/// Capitalizes an action for display, turning `kick` into `Kick`.
+pub fn action_name(action: &str) -> String {
+    let mut chars = action.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// # target_mention
+/// Mentions whatever a case was about. Purges, (un)locks and slowmode act on
+/// channels, everything else acts on users.
+fn target_mention(case: &Case) -> String {
+    match case.action.as_str() {
+        "purge" | "lock" | "unlock" | "slowmode" => format!("<#{}>", case.target),
+        _ => format!("<@{0}> (`{0}`)", case.target),
+    }
+}
@@ -0,0 +1,180 @@
+use regex::Regex;
+use serenity::{model::prelude::*, prelude::*};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    automod::{compile_pattern, is_zalgo},
+    commands::modtools::{dehoisted, rename_member},
+    dbx::{self, NameRules, ZweiDbConn},
+    guard::check,
+    modlog::log_case,
+};
+
+/// # ZweiNameRules
+/// A `TypeMapKey` caching each guild's name rules with their denied patterns
+/// compiled, as member updates come in a lot more often than rules change.
+/// Entries are dropped with [`invalidate`] whenever a guild changes its rules.
+pub struct ZweiNameRules;
+impl TypeMapKey for ZweiNameRules {
+    type Value = HashMap<u64, Arc<GuildNameRules>>;
+}
+
+/// # DeniedPattern
+/// A denied name pattern, ready to be matched against names.
+pub struct DeniedPattern {
+    pub id: i64,
+    pub pattern: String,
+    matcher: Regex,
+}
+
+/// # Verdict
+/// What's wrong with a name, if anything.
+pub enum Verdict {
+    /// The name breaks a rule and should be replaced by the placeholder. Holds
+    /// the rule it breaks.
+    Denied(String),
+    /// The name is hoisted. Holds the name without the leading symbols.
+    Hoisted(String),
+}
+
+/// # GuildNameRules
+/// Everything the name rules need to know about a guild.
+pub struct GuildNameRules {
+    pub rules: NameRules,
+    pub denied: Vec<DeniedPattern>,
+}
+
+impl GuildNameRules {
+    /// # enabled
+    /// Whether any of the rules are turned on.
+    pub fn enabled(&self) -> bool {
+        self.rules.dehoist || self.rules.ascii || self.rules.zalgo || !self.denied.is_empty()
+    }
+
+    /// # judge
+    /// Checks a name against the rules. Denied patterns go first, dehoisting
+    /// last, as there's no point dehoisting a name that gets replaced anyway.
+    pub fn judge(&self, name: &str) -> Option<Verdict> {
+        if let Some(denied) = self.denied.iter().find(|d| d.matcher.is_match(name)) {
+            return Some(Verdict::Denied(format!(
+                "Name matches denied pattern #{}",
+                denied.id
+            )));
+        }
+        if self.rules.zalgo && is_zalgo(name) {
+            return Some(Verdict::Denied("Name contains zalgo".to_owned()));
+        }
+        if self.rules.ascii && !name.chars().any(|c| c.is_ascii_alphanumeric()) {
+            return Some(Verdict::Denied("Name can't be typed".to_owned()));
+        }
+        match self.rules.dehoist {
+            true => self.dehoist(name).map(Verdict::Hoisted),
+            false => None,
+        }
+    }
+
+    /// # dehoist
+    /// Strips the leading symbols from a hoisted name, falling back to the
+    /// placeholder if nothing is left. Returns `None` if it wasn't hoisted.
+    pub fn dehoist(&self, name: &str) -> Option<String> {
+        dehoisted(name).map(|stripped| match stripped.is_empty() {
+            true => self.rules.placeholder.clone(),
+            false => stripped,
+        })
+    }
+}
+
+/// # invalidate
+/// Drops the cached name rules for a guild, so they're reloaded from the
+/// database next time. Call this after changing a guild's rules.
+pub async fn invalidate(ctx: &Context, guild: GuildId) {
+    if let Some(cache) = ctx.data.write().await.get_mut::<ZweiNameRules>() {
+        cache.remove(&guild.0);
+    }
+}
+
+/// # rules_for
+/// Gets a guild's name rules, loading and compiling them if they aren't
+/// cached yet.
+pub async fn rules_for(ctx: &Context, guild: GuildId) -> Option<Arc<GuildNameRules>> {
+    if let Some(cached) = ctx
+        .data
+        .read()
+        .await
+        .get::<ZweiNameRules>()
+        .and_then(|cache| cache.get(&guild.0))
+    {
+        return Some(cached.clone());
+    }
+    let loaded = {
+        let botdata = ctx.data.read().await;
+        let conn = botdata.get::<ZweiDbConn>()?;
+        let rules = dbx::get_namerules(conn, guild.0).await.ok()?;
+        let denied = dbx::get_name_denylist(conn, guild.0).await.ok()?;
+        Arc::new(GuildNameRules {
+            rules,
+            denied: denied
+                .into_iter()
+                .filter_map(|d| match compile_pattern("regex", &d.pattern) {
+                    Ok(matcher) => Some(DeniedPattern {
+                        id: d.patternid,
+                        pattern: d.pattern,
+                        matcher,
+                    }),
+                    Err(e) => {
+                        log::warn!("Skipping denied name pattern {}\n\t{e}", d.patternid);
+                        None
+                    }
+                })
+                .collect(),
+        })
+    };
+    ctx.data
+        .write()
+        .await
+        .entry::<ZweiNameRules>()
+        .or_default()
+        .insert(guild.0, loaded.clone());
+    Some(loaded)
+}
+
+/// # enforce_name
+/// Renames a member whose display name breaks the guild's name rules. Names
+/// that break a rule are replaced by the placeholder and logged as a case,
+/// hoisted names just lose their leading symbols. Called when members join or
+/// change their name. Returns whether the member was renamed.
+pub async fn enforce_name(ctx: &Context, member: &Member) -> bool {
+    if member.user.bot {
+        return false;
+    }
+    let guild = member.guild_id;
+    let rules = match rules_for(ctx, guild).await {
+        Some(rules) if rules.enabled() => rules,
+        _ => return false,
+    };
+    let name = member.display_name();
+    let (nick, reason) = match rules.judge(&name) {
+        Some(Verdict::Denied(why)) => (rules.rules.placeholder.clone(), Some(why)),
+        Some(Verdict::Hoisted(nick)) => (nick, None),
+        None => return false,
+    };
+    // A placeholder that breaks the rules itself shouldn't keep renaming.
+    if nick == *name || check(ctx, guild, None, member.user.id).await.is_err() {
+        return false;
+    }
+    let audit = reason.as_deref().unwrap_or("Dehoisted");
+    if let Err(e) = rename_member(ctx, guild, member.user.id, &nick, audit).await {
+        log::warn!(
+            "Couldn't enforce the name rules on {} in {}\n\t{e}",
+            member.user.id.0,
+            guild.0
+        );
+        return false;
+    }
+    if let Some(why) = reason {
+        let me = ctx.cache.current_user_id();
+        let reason = format!("{why}, renamed from `{}`", name.replace('`', "'"));
+        log_case(ctx, guild, me, member.user.id.0, "nick", &reason).await;
+    }
+    true
+}
@@ -0,0 +1,194 @@
+use chrono::Utc;
+use serenity::{
+    framework::standard::CommandResult,
+    http::{Http, StatusCode},
+    model::id::ChannelId,
+    prelude::*,
+    Error,
+};
+use std::{collections::BTreeMap, error::Error as StdError, sync::Arc};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender},
+    time::{sleep, Duration},
+};
+
+use crate::{
+    commands::modtools::unlock_channel,
+    dbx::{self, Pool, ScheduledAction, ZweiDbConn, ZweiDbRes},
+};
+
+/// How long to wait before retrying a failed action the first time, in
+/// seconds. Doubles with every failure, up to `RETRY_MAX`.
+const RETRY_BASE: i64 = 60;
+/// The longest to wait between retries, in seconds.
+const RETRY_MAX: i64 = 3600;
+/// The furthest ahead an action may be scheduled, in seconds, which is a year.
+/// Commands taking a duration refuse anything longer than this.
+pub const MAX_DELAY: u64 = 365 * 86400;
+/// How often an action may fail before it's given up on. With the waits
+/// above, that's about a day of retrying.
+const MAX_ATTEMPTS: i64 = 30;
+
+/// # ZweiScheduler
+/// A `TypeMapKey` holding the sending half of the scheduler's queue.
+/// Anything sent through here has to be stored in the database first, so it
+/// survives a restart. Use [`schedule`] rather than sending directly.
+pub struct ZweiScheduler;
+impl TypeMapKey for ZweiScheduler {
+    type Value = UnboundedSender<ScheduledAction>;
+}
+
+/// # schedule
+/// Stores an action to perform `secs` seconds from now and queues it up in the
+/// running scheduler. If the scheduler can't be reached, the action is still
+/// picked up from the database the next time Zwei connects. `data` is only
+/// used by actions that need to remember something, use 0 otherwise.
+pub async fn schedule(
+    ctx: &Context,
+    guild: u64,
+    target: u64,
+    action: &str,
+    secs: u64,
+    data: i64,
+) -> ZweiDbRes<ScheduledAction> {
+    let due = i64::try_from(secs)
+        .ok()
+        .and_then(|secs| Utc::now().timestamp().checked_add(secs))
+        .ok_or_else(|| dbx::SQLerr::Protocol(format!("{secs} seconds from now is too far off")))?;
+    let botdata = ctx.data.read().await;
+    let conn = botdata.get::<ZweiDbConn>().ok_or(dbx::SQLerr::PoolClosed)?;
+    let scheduled = dbx::add_scheduled(conn, guild, target, action, due, data).await?;
+    match botdata.get::<ZweiScheduler>() {
+        Some(tx) if tx.send(scheduled.clone()).is_ok() => (),
+        _ => log::error!(
+            "Couldn't reach the scheduler, action {} will wait for a reconnect",
+            scheduled.actionid
+        ),
+    };
+    Ok(scheduled)
+}
+
+/// # run
+/// The scheduler itself, meant to be spawned as a task from `main`. It keeps
+/// pending actions ordered by when they're due, sleeping until the first one
+/// is due or until a new action comes in. Every action is claimed from the
+/// database right before it's performed, so cancelled actions are skipped and
+/// actions queued twice (e.g. after a reconnect) only run once. Actions that
+/// fail for a reason that might pass are put back to be tried again later.
+pub async fn run(http: Arc<Http>, conn: Pool, mut rx: UnboundedReceiver<ScheduledAction>) {
+    let mut pending: BTreeMap<(i64, i64), ScheduledAction> = BTreeMap::new();
+    loop {
+        // Nothing to do means waiting for the next action to come in.
+        let wait = pending
+            .keys()
+            .next()
+            .map(|(due, _)| (*due - Utc::now().timestamp()).max(0) as u64)
+            .unwrap_or(3600);
+        tokio::select! {
+            received = rx.recv() => match received {
+                Some(action) => {
+                    pending.insert((action.due, action.actionid), action);
+                }
+                None => {
+                    log::warn!("Scheduler queue closed, stopping the scheduler");
+                    break;
+                }
+            },
+            _ = sleep(Duration::from_secs(wait)) => {
+                let now = Utc::now().timestamp();
+                while let Some(entry) = pending.first_entry() {
+                    if entry.key().0 > now {
+                        break;
+                    }
+                    if let Some(retry) = perform(&http, &conn, entry.remove()).await {
+                        pending.insert((retry.due, retry.actionid), retry);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// # is_permanent
+/// Tells whether a failed action should be given up on right away. Discord
+/// refusing a request (a 4xx other than being rate limited, like the ban
+/// already being gone) won't change by trying again, anything else might.
+fn is_permanent(err: &(dyn StdError + Send + Sync + 'static)) -> bool {
+    match err.downcast_ref::<Error>() {
+        Some(Error::Http(e)) => e
+            .status_code()
+            .is_some_and(|s| s.is_client_error() && s != StatusCode::TOO_MANY_REQUESTS),
+        _ => false,
+    }
+}
+
+/// # perform
+/// Claims and executes a single due action. Failures are logged, as there's
+/// nobody around to report them to at this point. If the action failed for a
+/// reason that might pass, it's stored again with a backoff and returned so
+/// it can be queued up again.
+async fn perform(http: &Http, conn: &Pool, action: ScheduledAction) -> Option<ScheduledAction> {
+    match dbx::claim_scheduled(conn, action.actionid).await {
+        Ok(1..) => (),
+        Ok(_) => {
+            log::trace!("Skipping cancelled action {}", action.actionid);
+            return None;
+        }
+        Err(_) => return None,
+    };
+    let guild = action.serverid as u64;
+    let target = action.target as u64;
+    let res: CommandResult = match action.action.as_str() {
+        "unban" => http
+            .remove_ban(guild, target, Some("Temporary ban expired"))
+            .await
+            .map_err(Into::into),
+        "unmute" => unmute(http, conn, guild, target).await,
+        "unlock" => unlock_channel(http, conn, guild, target).await.map(|_| ()),
+        "slowmode" => ChannelId(target)
+            .edit(http, |c| c.rate_limit_per_user(action.data as u64))
+            .await
+            .map(|_| ())
+            .map_err(Into::into),
+        other => {
+            log::error!("Unknown scheduled action {other}, dropping it");
+            Ok(())
+        }
+    };
+    let e = res.err()?;
+    if is_permanent(e.as_ref()) || action.attempts + 1 >= MAX_ATTEMPTS {
+        log::error!(
+            "Scheduled {} on {target} in guild {guild} failed, giving up!\n\t{e}",
+            action.action
+        );
+        return None;
+    }
+    let wait = (RETRY_BASE << action.attempts.min(16)).min(RETRY_MAX);
+    log::warn!(
+        "Scheduled {} on {target} in guild {guild} failed, retrying in {wait}s\n\t{e}",
+        action.action
+    );
+    dbx::retry_scheduled(conn, &action, Utc::now().timestamp() + wait)
+        .await
+        .ok()
+}
+
+/// # unmute
+/// Takes the muted role off a member whose mute expired. The mute is only
+/// forgotten once the role is gone, so it's reapplied if they rejoin while
+/// the role couldn't be removed.
+async fn unmute(http: &Http, conn: &Pool, guild: u64, target: u64) -> CommandResult {
+    let role = dbx::get_mute_role(conn, guild).await?;
+    if role != 0 {
+        match http
+            .remove_member_role(guild, target, role, Some("Mute expired"))
+            .await
+        {
+            // Members who left or a deleted role leave nothing to take off
+            Err(Error::Http(e)) if e.status_code() == Some(StatusCode::NOT_FOUND) => (),
+            res => res?,
+        }
+    }
+    dbx::remove_mute(conn, guild, target).await?;
+    Ok(())
+}
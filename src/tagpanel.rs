@@ -0,0 +1,234 @@
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    framework::standard::CommandResult,
+    http::StatusCode,
+    model::{
+        application::interaction::{
+            message_component::MessageComponentInteraction, InteractionResponseType,
+        },
+        prelude::*,
+    },
+    prelude::*,
+    utils::Color,
+    Error,
+};
+use std::collections::HashSet;
+
+use crate::{
+    commands::subs::is_emoji,
+    dbx::{self, TagInfo, ZweiDbConn},
+    get_color, zwei_conf,
+};
+
+/// Discord allows this many options per menu.
+const PER_MENU: usize = 25;
+/// Discord allows this many menus per message.
+const MAX_MENUS: usize = 5;
+
+/// # panel_embed
+/// Fills in the embed explaining how to use a tag panel.
+fn panel_embed<'a>(e: &'a mut CreateEmbed, tags: &[TagInfo], color: Color) -> &'a mut CreateEmbed {
+    let mut text = match tags.len() {
+        0 => "This server has no tags yet.".to_owned(),
+        _ => "Pick tags below to subscribe to them, or pick ones you're already subscribed to to unsubscribe. Only you get to see what changed.".to_owned(),
+    };
+    if tags.len() > PER_MENU * MAX_MENUS {
+        text.push_str(&format!(
+            "\n\nOnly the first {} tags fit here, use `tag sub` for the others.",
+            PER_MENU * MAX_MENUS
+        ));
+    }
+    e.color(color).title("Tag subscriptions").description(text)
+}
+
+/// # panel_components
+/// Fills in the menus of a tag panel, up to 25 tags per menu. Emoji are left
+/// out if `with_emoji` is false, or if they don't look like one.
+fn panel_components<'a>(
+    c: &'a mut CreateComponents,
+    tags: &[TagInfo],
+    with_emoji: bool,
+) -> &'a mut CreateComponents {
+    for (i, chunk) in tags.chunks(PER_MENU).take(MAX_MENUS).enumerate() {
+        c.create_action_row(|row| {
+            row.create_select_menu(|menu| {
+                menu.custom_id(format!("tagpanel:{i}"))
+                    .placeholder(match i {
+                        0 => "Pick tags to toggle".to_owned(),
+                        _ => format!("Pick tags to toggle ({})", i + 1),
+                    })
+                    .min_values(1)
+                    .max_values(chunk.len() as u64)
+                    .options(|opts| {
+                        for tag in chunk {
+                            opts.create_option(|opt| {
+                                opt.label(&tag.tagname).value(&tag.tagname);
+                                if !tag.description.is_empty() {
+                                    opt.description(&tag.description);
+                                }
+                                if with_emoji && is_emoji(&tag.emoji) {
+                                    if let Ok(emoji) = ReactionType::try_from(tag.emoji.as_str()) {
+                                        opt.emoji(emoji);
+                                    }
+                                }
+                                opt
+                            });
+                        }
+                        opts
+                    })
+            })
+        });
+    }
+    c
+}
+
+/// # is_bad_request
+/// Checks whether Discord refused a panel outright, which is what happens when
+/// a tag has an emoji it doesn't accept.
+fn is_bad_request<T>(res: &Result<T, Error>) -> bool {
+    matches!(res, Err(Error::Http(e)) if e.status_code() == Some(StatusCode::BAD_REQUEST))
+}
+
+/// # post_panel
+/// Posts a new tag panel in a channel and remembers it.
+pub async fn post_panel(ctx: &Context, guild: GuildId, channel: ChannelId) -> CommandResult {
+    let conn = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn.clone(),
+            None => return Err("no database connection".into()),
+        }
+    };
+    let tags = dbx::get_tag_infos(&conn, guild.0).await?;
+    let color = get_color(&zwei_conf::CONF.ok_color)?;
+    let mut panel = channel
+        .send_message(ctx, |m| {
+            m.embed(|e| panel_embed(e, &tags, color))
+                .components(|c| panel_components(c, &tags, true))
+        })
+        .await;
+    if is_bad_request(&panel) {
+        log::warn!(
+            "Discord refused the tag panel for {}, leaving out emoji",
+            guild.0
+        );
+        panel = channel
+            .send_message(ctx, |m| {
+                m.embed(|e| panel_embed(e, &tags, color))
+                    .components(|c| panel_components(c, &tags, false))
+            })
+            .await;
+    }
+    let panel = panel?;
+    dbx::add_tag_panel(&conn, guild.0, channel.0, panel.id.0).await?;
+    Ok(())
+}
+
+/// # refresh_panels
+/// Brings the tag panels of a guild up to date with its tags, or those of all
+/// guilds if none is given. Panels whose message was deleted are forgotten.
+/// Called whenever tags change, and on startup.
+pub async fn refresh_panels(ctx: &Context, guild: Option<GuildId>) {
+    let conn = {
+        let botdata = ctx.data.read().await;
+        match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn.clone(),
+            None => return,
+        }
+    };
+    let panels = match dbx::get_tag_panels(&conn, guild.map(|g| g.0)).await {
+        Ok(panels) => panels,
+        Err(e) => {
+            log::error!("Couldn't load tag panels\n\t{e}");
+            return;
+        }
+    };
+    let color = match get_color(&zwei_conf::CONF.ok_color) {
+        Ok(color) => color,
+        Err(_) => return,
+    };
+    for panel in panels {
+        let tags = match dbx::get_tag_infos(&conn, panel.serverid as u64).await {
+            Ok(tags) => tags,
+            Err(_) => continue,
+        };
+        let channel = ChannelId(panel.channelid as u64);
+        let mut edited = channel
+            .edit_message(ctx, panel.messageid as u64, |m| {
+                m.embed(|e| panel_embed(e, &tags, color))
+                    .components(|c| panel_components(c, &tags, true))
+            })
+            .await;
+        if is_bad_request(&edited) {
+            log::warn!(
+                "Discord refused tag panel {}, leaving out emoji",
+                panel.messageid
+            );
+            edited = channel
+                .edit_message(ctx, panel.messageid as u64, |m| {
+                    m.embed(|e| panel_embed(e, &tags, color))
+                        .components(|c| panel_components(c, &tags, false))
+                })
+                .await;
+        }
+        match edited {
+            Err(Error::Http(e)) if e.status_code() == Some(StatusCode::NOT_FOUND) => {
+                log::info!("Tag panel {} is gone, forgetting it", panel.messageid);
+                let _ = dbx::remove_tag_panel(&conn, panel.messageid as u64).await;
+            }
+            Err(e) => log::warn!("Couldn't refresh tag panel {}\n\t{e}", panel.messageid),
+            Ok(_) => (),
+        }
+    }
+}
+
+/// # on_select
+/// Handles picking tags on a tag panel. Every picked tag is toggled, so
+/// tags the member was subscribed to get unsubscribed and the other way
+/// around. The member gets a confirmation only they can see.
+pub async fn on_select(ctx: &Context, press: &MessageComponentInteraction) {
+    let guild = match press.guild_id {
+        Some(guild) => guild.0,
+        None => return,
+    };
+    let user = press.user.id.0;
+    let mut lines = Vec::with_capacity(press.data.values.len());
+    {
+        let botdata = ctx.data.read().await;
+        let conn = match botdata.get::<ZweiDbConn>() {
+            Some(conn) => conn,
+            None => {
+                log::error!("Failed to acquire database connection object to toggle tags!");
+                return;
+            }
+        };
+        let subbed: HashSet<String> = dbx::usersubs(conn, guild, user)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        for tag in &press.data.values {
+            let line = if subbed.contains(tag) {
+                match dbx::unsub(conn, guild, tag, user).await {
+                    Ok(_) => format!("- Unsubscribed from {tag}"),
+                    Err(_) => format!("Couldn't unsubscribe you from {tag}"),
+                }
+            } else {
+                match dbx::sub_to(conn, guild, tag, user).await {
+                    Ok(_) => format!("+ Subscribed to {tag}"),
+                    Err(_) => format!("Couldn't find {tag} anymore"),
+                }
+            };
+            lines.push(line);
+        }
+    }
+    if let Err(e) = press
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(lines.join("\n")).ephemeral(true))
+        })
+        .await
+    {
+        log::warn!("Couldn't confirm toggling tags for {user}\n\t{e}");
+    }
+}
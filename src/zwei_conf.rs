@@ -66,6 +66,7 @@ pub(crate) static CONF: Lazy<Conf> = Lazy::new(|| read_conf().unwrap());
 /// its config files. The places searched are
 /// - a `data` folder living next to the executable
 /// - a `data` folder in the current working directory
+///
 /// If neither of these places contain this folder, the bot will attempt to
 /// create this directory instead. Make sure that Zwei has write permissions
 /// if you plan to use this mechanism to generate the data folder.
@@ -87,9 +88,9 @@ fn get_data_dir() -> PathBuf {
     }
 
     if fs::create_dir_all(&preferred).is_ok() {
-        return preferred;
+        preferred
     } else if fs::create_dir_all(&fallback).is_ok() {
-        return fallback;
+        fallback
     } else {
         panic!(
             "Can't create {} or {}. Please create a data folder yourself!",